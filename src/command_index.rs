@@ -126,7 +126,10 @@ pub fn rebuild_index(plugins_dir: &Path) -> Result<(), HostError> {
     for entry in entries.flatten() {
         let path = entry.path();
         let name = entry.file_name();
-        let name_str = name.to_string_lossy();
+        // Non-UTF-8 names can't be plugin IDs; a lossy copy would point elsewhere
+        let Some(name_str) = name.to_str() else {
+            continue;
+        };
 
         if name_str == COMMANDS_DIR_NAME || !path.is_dir() {
             continue;
@@ -138,8 +141,8 @@ pub fn rebuild_index(plugins_dir: &Path) -> Result<(), HostError> {
         };
         let version = version.trim();
 
-        let _ = update_latest_link(plugins_dir, &name_str, version);
-        let _ = create_command_symlinks(plugins_dir, &name_str, version);
+        let _ = update_latest_link(plugins_dir, name_str, version);
+        let _ = create_command_symlinks(plugins_dir, name_str, version);
     }
 
     Ok(())
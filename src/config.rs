@@ -1,6 +1,10 @@
 //! Plugin host configuration.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use lib_plugin_manifest::PluginManifest;
+
+use crate::{BinaryResolver, HostError};
 
 /// Configuration for the plugin host.
 #[derive(Debug, Clone)]
@@ -8,47 +12,102 @@ pub struct PluginConfig {
     /// Directory where plugins are installed
     pub plugins_dir: PathBuf,
 
+    /// Read-only, system-wide plugins directory scanned after `plugins_dir`
+    pub system_plugins_dir: Option<PathBuf>,
+
     /// Cache directory for downloads
     pub cache_dir: PathBuf,
 
     /// Registry URL (None = no registry, local only)
     pub registry_url: Option<String>,
 
+    /// Mirror registry URLs, tried in order when the primary registry fails
+    pub registry_mirrors: Vec<String>,
+
     /// Require signature verification
     pub require_signatures: bool,
 
+    /// Allow installing an older version over a newer one
+    pub allow_downgrade: bool,
+
     /// Trusted public keys (base64 encoded)
     pub trusted_keys: Vec<String>,
 
     /// Host application version (for compatibility checks)
     pub host_version: String,
+
+    /// Consecutive load failures after which a plugin is quarantined
+    pub quarantine_threshold: u32,
+
+    /// Largest `plugin.toml`, in bytes, that will be parsed
+    pub max_manifest_size: u64,
+
+    /// Plugin ID patterns that may be installed and loaded (None = any)
+    pub allowlist: Option<Vec<String>>,
+
+    /// Plugin ID patterns that may never be installed or loaded, even if allowlisted
+    pub blocklist: Vec<String>,
+
+    /// How plugin binaries are located inside their plugin directory
+    pub binary_resolver: BinaryResolver,
 }
 
 impl PluginConfig {
     /// Create a new configuration with required paths.
+    ///
+    /// Both paths go through [`expand_path`], so `~/...` and relative paths work.
     pub fn new(plugins_dir: PathBuf, cache_dir: PathBuf) -> Self {
         Self {
-            plugins_dir,
-            cache_dir,
+            plugins_dir: expand_path(&plugins_dir),
+            system_plugins_dir: None,
+            cache_dir: expand_path(&cache_dir),
             registry_url: None,
+            registry_mirrors: Vec::new(),
             require_signatures: false,
+            allow_downgrade: false,
             trusted_keys: Vec::new(),
             host_version: String::new(),
+            quarantine_threshold: crate::DEFAULT_QUARANTINE_THRESHOLD,
+            max_manifest_size: crate::DEFAULT_MAX_MANIFEST_SIZE,
+            allowlist: None,
+            blocklist: Vec::new(),
+            binary_resolver: BinaryResolver::default(),
         }
     }
 
+    /// Set a read-only system plugins directory.
+    ///
+    /// Plugins there are loaded unless the same ID is installed in `plugins_dir`.
+    /// Installs and uninstalls never touch it.
+    pub fn with_system_plugins_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.system_plugins_dir = Some(expand_path(&dir.into()));
+        self
+    }
+
     /// Set the registry URL.
     pub fn with_registry(mut self, url: impl Into<String>) -> Self {
         self.registry_url = Some(url.into());
         self
     }
 
+    /// Add a mirror registry, tried after the primary and any earlier mirrors.
+    pub fn with_registry_mirror(mut self, url: impl Into<String>) -> Self {
+        self.registry_mirrors.push(url.into());
+        self
+    }
+
     /// Enable signature verification.
     pub fn require_signatures(mut self, require: bool) -> Self {
         self.require_signatures = require;
         self
     }
 
+    /// Allow installs that replace a newer version with an older one.
+    pub fn allow_downgrade(mut self, allow: bool) -> Self {
+        self.allow_downgrade = allow;
+        self
+    }
+
     /// Add a trusted key.
     pub fn with_trusted_key(mut self, key: impl Into<String>) -> Self {
         self.trusted_keys.push(key.into());
@@ -67,10 +126,207 @@ impl PluginConfig {
         self
     }
 
+    /// Set how many consecutive load failures quarantine a plugin.
+    pub fn with_quarantine_threshold(mut self, threshold: u32) -> Self {
+        self.quarantine_threshold = threshold;
+        self
+    }
+
+    /// Set the largest `plugin.toml`, in bytes, that will be parsed.
+    ///
+    /// Bigger manifests are skipped when listing installed plugins.
+    pub fn with_max_manifest_size(mut self, bytes: u64) -> Self {
+        self.max_manifest_size = bytes;
+        self
+    }
+
+    /// Only permit plugin IDs matching one of these patterns (`*` wildcards, e.g. `vendor.*`).
+    pub fn with_allowlist(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowlist = Some(patterns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Never permit plugin IDs matching this pattern (`*` wildcards).
+    pub fn with_blocked(mut self, pattern: impl Into<String>) -> Self {
+        self.blocklist.push(pattern.into());
+        self
+    }
+
+    /// Set a custom binary lookup, tried before the default naming conventions.
+    pub fn with_binary_resolver(
+        mut self,
+        resolve: impl Fn(&PluginManifest, &Path) -> Option<PathBuf> + Send + Sync + 'static,
+    ) -> Self {
+        self.binary_resolver = BinaryResolver::with_custom(resolve);
+        self
+    }
+
+    /// Build a configuration from environment variables layered over the defaults.
+    ///
+    /// Reads `<PREFIX>_PLUGINS_DIR`, `_SYSTEM_PLUGINS_DIR`, `_CACHE_DIR`, `_REGISTRY_URL`,
+    /// `_REGISTRY_MIRRORS` (comma-separated), `_REQUIRE_SIGNATURES`,
+    /// `_TRUSTED_KEYS` (comma-separated), `_HOST_VERSION`, `_ALLOW_DOWNGRADE`,
+    /// `_QUARANTINE_THRESHOLD`, `_MAX_MANIFEST_SIZE`, `_ALLOWLIST` and
+    /// `_BLOCKLIST` (both comma-separated). Unset variables keep their defaults;
+    /// unparsable values are reported together as `HostError::InvalidConfig`.
+    pub fn from_env(prefix: &str) -> Result<Self, HostError> {
+        Self::from_vars(prefix, |name| std::env::var(name).ok())
+    }
+
+    fn from_vars(prefix: &str, var: impl Fn(&str) -> Option<String>) -> Result<Self, HostError> {
+        let var = |name: &str| var(&format!("{}_{}", prefix, name));
+        let list = |value: String| -> Vec<String> {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        if let Some(dir) = var("PLUGINS_DIR") {
+            config.plugins_dir = expand_path(Path::new(&dir));
+        }
+        if let Some(dir) = var("SYSTEM_PLUGINS_DIR") {
+            config.system_plugins_dir = Some(expand_path(Path::new(&dir)));
+        }
+        if let Some(dir) = var("CACHE_DIR") {
+            config.cache_dir = expand_path(Path::new(&dir));
+        }
+        if let Some(url) = var("REGISTRY_URL") {
+            config.registry_url = Some(url);
+        }
+        if let Some(mirrors) = var("REGISTRY_MIRRORS") {
+            config.registry_mirrors = list(mirrors);
+        }
+        if let Some(keys) = var("TRUSTED_KEYS") {
+            config.trusted_keys = list(keys);
+        }
+        if let Some(patterns) = var("ALLOWLIST") {
+            config.allowlist = Some(list(patterns));
+        }
+        if let Some(patterns) = var("BLOCKLIST") {
+            config.blocklist = list(patterns);
+        }
+        if let Some(version) = var("HOST_VERSION") {
+            config.host_version = version;
+        }
+        for (name, field) in [
+            ("REQUIRE_SIGNATURES", &mut config.require_signatures),
+            ("ALLOW_DOWNGRADE", &mut config.allow_downgrade),
+        ] {
+            if let Some(value) = var(name) {
+                match parse_bool(&value) {
+                    Some(b) => *field = b,
+                    None => errors.push(format!("{}_{} is not a boolean: {}", prefix, name, value)),
+                }
+            }
+        }
+        if let Some(value) = var("QUARANTINE_THRESHOLD") {
+            match value.parse() {
+                Ok(n) => config.quarantine_threshold = n,
+                Err(_) => errors.push(format!(
+                    "{}_QUARANTINE_THRESHOLD is not a number: {}",
+                    prefix, value
+                )),
+            }
+        }
+        if let Some(value) = var("MAX_MANIFEST_SIZE") {
+            match value.parse() {
+                Ok(n) => config.max_manifest_size = n,
+                Err(_) => errors.push(format!(
+                    "{}_MAX_MANIFEST_SIZE is not a number: {}",
+                    prefix, value
+                )),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(HostError::InvalidConfig(errors))
+        }
+    }
+
+    /// Validate and return the finished configuration.
+    pub fn build(self) -> Result<Self, HostError> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Check the configuration for mistakes that would otherwise only surface at first use.
+    ///
+    /// Every problem is reported, not just the first.
+    pub fn validate(&self) -> Result<(), HostError> {
+        let mut errors = Vec::new();
+
+        if self.plugins_dir.as_os_str().is_empty() {
+            errors.push("plugins_dir is empty".to_string());
+        }
+        for (name, dir) in [
+            ("plugins_dir", Some(&self.plugins_dir)),
+            ("system_plugins_dir", self.system_plugins_dir.as_ref()),
+            ("cache_dir", Some(&self.cache_dir)),
+        ] {
+            if let Some(dir) = dir
+                .map(|d| expand_path(d))
+                .filter(|d| d.exists() && !d.is_dir())
+            {
+                errors.push(format!("{} is not a directory: {}", name, dir.display()));
+            }
+        }
+        for url in self.registry_url.iter().chain(&self.registry_mirrors) {
+            if !is_valid_registry_url(url) {
+                errors.push(format!("Registry URL is not a valid http(s) URL: {}", url));
+            }
+        }
+        for key in &self.trusted_keys {
+            if !is_base64(key) {
+                errors.push(format!("Trusted key is not valid base64: {}", key));
+            }
+        }
+        if self.require_signatures {
+            if self.trusted_keys.is_empty() {
+                errors.push("Signatures are required but no trusted keys are set".to_string());
+            }
+            if self.host_version.is_empty() {
+                errors.push("Signatures are required but host_version is empty".to_string());
+            }
+        }
+        if self.quarantine_threshold == 0 {
+            errors.push("quarantine_threshold must be at least 1".to_string());
+        }
+        if self.max_manifest_size == 0 {
+            errors.push("max_manifest_size must be at least 1".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(HostError::InvalidConfig(errors))
+        }
+    }
+
     /// Ensure directories exist.
+    ///
+    /// Paths are expanded with [`expand_path`] first, so a literal `~` directory
+    /// is never created. A path that exists as a regular file is reported by name.
     pub fn ensure_dirs(&self) -> std::io::Result<()> {
-        std::fs::create_dir_all(&self.plugins_dir)?;
-        std::fs::create_dir_all(&self.cache_dir)?;
+        for (name, dir) in [
+            ("plugins_dir", &self.plugins_dir),
+            ("cache_dir", &self.cache_dir),
+        ] {
+            let dir = expand_path(dir);
+            if dir.exists() && !dir.is_dir() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotADirectory,
+                    format!("{} is not a directory: {}", name, dir.display()),
+                ));
+            }
+            std::fs::create_dir_all(dir)?;
+        }
         Ok(())
     }
 }
@@ -93,15 +349,210 @@ impl PluginConfig {
     }
 }
 
+/// Expand a leading `~` to the home directory and make the path absolute.
+///
+/// Relative paths are resolved against the current directory. Paths that
+/// already exist are canonicalized, so every caller sees the same spelling.
+pub fn expand_path(path: &Path) -> PathBuf {
+    let expanded = match path.strip_prefix("~") {
+        Ok(rest) => match dirs::home_dir() {
+            Some(home) => home.join(rest),
+            None => path.to_path_buf(),
+        },
+        Err(_) => path.to_path_buf(),
+    };
+    let absolute = if expanded.is_absolute() {
+        expanded
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(&expanded))
+            .unwrap_or(expanded)
+    };
+    absolute.canonicalize().unwrap_or(absolute)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn is_valid_registry_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !host.is_empty() && !host.contains(char::is_whitespace)
+}
+
+fn is_base64(s: &str) -> bool {
+    let data = s.trim_end_matches('=');
+    !s.is_empty()
+        && s.len().is_multiple_of(4)
+        && s.len() - data.len() <= 2
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+}
+
 impl Default for PluginConfig {
     fn default() -> Self {
         Self {
             plugins_dir: Self::default_plugins_dir(),
+            system_plugins_dir: None,
             cache_dir: Self::default_cache_dir(),
             registry_url: None,
+            registry_mirrors: Vec::new(),
             require_signatures: false,
+            allow_downgrade: false,
             trusted_keys: Vec::new(),
             host_version: String::new(),
+            quarantine_threshold: crate::DEFAULT_QUARANTINE_THRESHOLD,
+            max_manifest_size: crate::DEFAULT_MAX_MANIFEST_SIZE,
+            allowlist: None,
+            blocklist: Vec::new(),
+            binary_resolver: BinaryResolver::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(PluginConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let config = PluginConfig::default()
+            .with_registry("registry.example.com")
+            .with_trusted_key("not base64!")
+            .require_signatures(true)
+            .with_quarantine_threshold(0);
+
+        let Err(HostError::InvalidConfig(errors)) = config.validate() else {
+            panic!("expected InvalidConfig");
+        };
+        assert_eq!(errors.len(), 4);
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            expand_path(Path::new("~/.myapp/plugins")),
+            home.join(".myapp/plugins")
+        );
+        assert_eq!(
+            expand_path(Path::new("plugins")),
+            std::env::current_dir().unwrap().join("plugins")
+        );
+
+        let tmp = tempfile::tempdir().unwrap();
+        let absolute = tmp.path().canonicalize().unwrap();
+        assert_eq!(expand_path(&absolute), absolute);
+        assert_eq!(
+            expand_path(&absolute.join("missing")),
+            absolute.join("missing")
+        );
+    }
+
+    #[test]
+    fn test_ensure_dirs_expands_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"));
+        config.ensure_dirs().unwrap();
+
+        assert!(config.plugins_dir.is_dir());
+        assert!(config.cache_dir.is_dir());
+    }
+
+    #[test]
+    fn test_plugins_dir_is_a_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("plugins");
+        std::fs::write(&file, "").unwrap();
+        let config = PluginConfig::new(file, tmp.path().join("cache"));
+
+        let Err(HostError::InvalidConfig(errors)) = config.validate() else {
+            panic!("expected InvalidConfig");
+        };
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("plugins_dir is not a directory"));
+
+        let err = config.ensure_dirs().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotADirectory);
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: std::collections::HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_from_vars_layers_over_defaults() {
+        let tmp = tempfile::tempdir().unwrap();
+        let plugins_dir = tmp.path().join("plugins");
+        let config = PluginConfig::from_vars(
+            "MYAPP",
+            vars(&[
+                ("MYAPP_PLUGINS_DIR", plugins_dir.to_str().unwrap()),
+                ("MYAPP_REGISTRY_URL", "https://plugins.example.com"),
+                (
+                    "MYAPP_REGISTRY_MIRRORS",
+                    "https://a.example.com, https://b.example.com",
+                ),
+                ("MYAPP_ALLOW_DOWNGRADE", "yes"),
+                ("MYAPP_QUARANTINE_THRESHOLD", "5"),
+                ("MYAPP_BLOCKLIST", "vendor.*"),
+            ]),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+        assert_eq!(config.plugins_dir, plugins_dir);
+        assert_eq!(config.cache_dir, PluginConfig::default_cache_dir());
+        assert_eq!(config.registry_mirrors.len(), 2);
+        assert!(config.allow_downgrade);
+        assert!(!config.require_signatures);
+        assert_eq!(config.quarantine_threshold, 5);
+        assert_eq!(config.allowlist, None);
+        assert_eq!(config.blocklist, vec!["vendor.*".to_string()]);
+    }
+
+    #[test]
+    fn test_from_vars_rejects_bad_values() {
+        let result = PluginConfig::from_vars(
+            "MYAPP",
+            vars(&[
+                ("MYAPP_REQUIRE_SIGNATURES", "maybe"),
+                ("MYAPP_QUARANTINE_THRESHOLD", "-1"),
+            ]),
+        );
+        let Err(HostError::InvalidConfig(errors)) = result else {
+            panic!("expected InvalidConfig");
+        };
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_is_base64() {
+        assert!(is_base64("dGVzdA=="));
+        assert!(is_base64("dGVzdGtleQ+/"));
+        assert!(!is_base64("dGVzdA="));
+        assert!(!is_base64("dGV=zdA="));
+        assert!(!is_base64(""));
+    }
+}
@@ -21,6 +21,98 @@ pub enum HostError {
     #[error("Plugin not installed: {0}")]
     NotInstalled(String),
 
+    /// `.version` names a version directory that is missing or empty
+    #[error("Plugin {id} points at missing version directory {version}")]
+    DanglingVersion {
+        /// Plugin ID
+        id: String,
+        /// Version named by `.version`
+        version: String,
+    },
+
+    /// Installed files no longer match the checksums recorded at install time
+    #[error("Integrity check failed for {id}: {}", files.join(", "))]
+    IntegrityMismatch {
+        /// Plugin ID
+        id: String,
+        /// Files that changed or went missing, relative to the version directory
+        files: Vec<String>,
+    },
+
+    /// Downloaded archive does not match the expected checksum
+    #[error("Checksum mismatch for {id}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        /// Plugin ID
+        id: String,
+        /// Expected hex-encoded SHA-256
+        expected: String,
+        /// SHA-256 of the bytes actually received
+        actual: String,
+    },
+
+    /// Installing would replace a newer version with an older one
+    #[error("Refusing to downgrade {id} from {from} to {to}")]
+    DowngradeBlocked {
+        /// Plugin ID
+        id: String,
+        /// Currently installed version
+        from: String,
+        /// Requested version
+        to: String,
+    },
+
+    /// Another installer holds the plugin's install lock
+    #[error("Install already in progress for {0}")]
+    InstallInProgress(String),
+
+    /// Other installed plugins depend on the plugin being removed
+    #[error("Cannot uninstall {package}: required by {}", required_by.join(", "))]
+    DependencyInUse {
+        /// Plugin being uninstalled
+        package: String,
+        /// Installed plugins that declare it in `depends_on`
+        required_by: Vec<String>,
+    },
+
+    /// Plugin dependencies form a cycle
+    #[error("Dependency cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+
+    /// Plugin failed to load too many times in a row and is no longer auto-loaded
+    #[error("Plugin quarantined after repeated load failures: {0}")]
+    Quarantined(String),
+
+    /// A blocking wrapper was called from inside an async runtime
+    #[error("Blocking call from within an async runtime: {0}")]
+    BlockingInRuntime(String),
+
+    /// Named middleware are not registered
+    #[error("Middleware not registered: {}", .0.join(", "))]
+    MissingMiddleware(Vec<String>),
+
+    /// Configuration is invalid; one message per problem
+    #[error("Invalid configuration: {}", .0.join("; "))]
+    InvalidConfig(Vec<String>),
+
+    /// Plugin ID is blocked, or not on the allowlist
+    #[error("Plugin not permitted by policy: {0}")]
+    PluginNotPermitted(String),
+
+    /// Plugin lives in the read-only system plugins directory
+    #[error("Plugin is installed in the read-only system directory: {0}")]
+    ReadOnlyPlugin(String),
+
+    /// Manifest file is larger than the configured limit
+    #[error("Manifest {} is {size} bytes, over the {limit} byte limit", path.display())]
+    ManifestTooLarge {
+        /// Path to the manifest
+        path: std::path::PathBuf,
+        /// Size of the file in bytes
+        size: u64,
+        /// Configured limit in bytes
+        limit: u64,
+    },
+
     /// Failed to load plugin library
     #[error("Failed to load plugin: {0}")]
     LoadFailed(String),
@@ -45,6 +137,10 @@ pub enum HostError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// Downloaded archive is not gzip, zstd or xz compressed
+    #[error("Unsupported archive format: {0}")]
+    UnsupportedArchiveFormat(String),
+
     /// Platform not supported
     #[error("Platform not supported: {0}")]
     PlatformNotSupported(String),
@@ -3,6 +3,7 @@
 use std::path::PathBuf;
 
 use lib_plugin_manifest::{Manifest, PluginManifest};
+use serde::{Deserialize, Serialize};
 
 /// An installed package (may contain 1+ plugins).
 #[derive(Debug, Clone)]
@@ -67,6 +68,69 @@ impl InstalledPlugin {
     }
 }
 
+/// Which plugins directory an installed plugin was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginSource {
+    /// The writable per-user `plugins_dir`
+    User,
+    /// The read-only `system_plugins_dir`
+    System,
+}
+
+/// Several installed directories whose manifests declare the same plugin ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicatePluginId {
+    /// The shared plugin ID
+    pub id: String,
+    /// Version directories declaring it, in install-directory order
+    pub paths: Vec<PathBuf>,
+}
+
+/// A single check performed before enabling a plugin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightCheck {
+    /// Short check name, e.g. `"binary"` or `"dependencies"`
+    pub name: &'static str,
+    /// Whether the check passed
+    pub passed: bool,
+    /// Why the check failed
+    pub detail: Option<String>,
+}
+
+/// Outcome of checking whether an installed plugin can be enabled, without loading it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnablePreflight {
+    /// Plugin ID
+    pub plugin_id: String,
+    /// Every check, in the order performed
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl EnablePreflight {
+    /// Check if every preflight check passed.
+    pub fn can_enable(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    /// The checks that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &PreflightCheck> {
+        self.checks.iter().filter(|c| !c.passed)
+    }
+}
+
+/// Step of an install in progress, in the order they happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallPhase {
+    /// Fetching the archive from the registry
+    Downloading,
+    /// Checking the archive checksum
+    Verifying,
+    /// Unpacking the archive into the version directory
+    Extracting,
+    /// Writing `.version`, checksums and symlinks
+    Finalizing,
+}
+
 /// Install status for ongoing operations.
 #[derive(Debug, Clone)]
 pub enum InstallStatus {
@@ -74,7 +138,9 @@ pub enum InstallStatus {
     NotInstalled,
     /// Currently installing
     Installing {
-        /// Progress 0.0 to 1.0
+        /// Current step
+        phase: InstallPhase,
+        /// Progress within `phase`, 0.0 to 1.0
         progress: f32,
     },
     /// Installed successfully
@@ -110,3 +176,68 @@ impl InstallStatus {
         matches!(self, InstallStatus::UpdateAvailable { .. })
     }
 }
+
+/// Provenance of an installed plugin, written next to `.version` at install time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallRecord {
+    /// Plugin ID
+    pub id: String,
+    /// Installed version
+    pub version: String,
+    /// Install time, seconds since the Unix epoch
+    pub installed_at: u64,
+    /// Registry URL the archive was downloaded from
+    pub source: String,
+    /// Whether the archive's signature was verified
+    ///
+    /// Always `false` for now: the installer does not check signatures yet.
+    /// Kept so records stay readable once it does.
+    pub verified: bool,
+    /// Hex-encoded SHA-256 of the downloaded archive
+    pub checksum: String,
+}
+
+/// Exact installed versions and archive checksums, for reproducible installs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Locked plugins, sorted by ID
+    pub plugins: Vec<LockedPlugin>,
+}
+
+/// One pinned plugin in a [`Lockfile`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPlugin {
+    /// Plugin ID
+    pub id: String,
+    /// Exact version
+    pub version: String,
+    /// Hex-encoded SHA-256 of the registry archive
+    pub checksum: String,
+}
+
+/// Snapshot of installed plugins and their configuration, for backup and migration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostStateSnapshot {
+    /// Installed plugins
+    pub plugins: Vec<PluginSnapshot>,
+}
+
+/// One installed plugin in a [`HostStateSnapshot`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginSnapshot {
+    /// Plugin ID
+    pub id: String,
+    /// Installed version
+    pub version: String,
+    /// Contents of the plugin's `config.json`, if it has one
+    pub config: Option<serde_json::Value>,
+}
+
+/// Outcome of restoring a [`HostStateSnapshot`].
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Plugins installed from the registry to match the snapshot
+    pub installed: Vec<String>,
+    /// Plugins whose version or config could not be restored
+    pub failed: Vec<(String, crate::HostError)>,
+}
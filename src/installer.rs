@@ -2,13 +2,43 @@
 //!
 //! Contains no UI logic. Callers handle progress bars, i18n messages, and prompts.
 
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
 
 use lib_plugin_manifest::PluginManifest;
-use registry_client::{PluginEntry, PluginInfo, RegistryClient, SearchKind, SearchResults};
+use registry_client::{PluginEntry, PluginInfo, RegistryClient, SearchResults};
+use sha2::{Digest, Sha256};
+use tracing::Instrument;
 
-use crate::HostError;
+use crate::{
+    DuplicatePluginId, HostError, HostStateSnapshot, ImportReport, InstallPhase, InstallRecord,
+    InstallStatus, InstalledPlugin, LockedPlugin, Lockfile, ManifestCache, ManifestCacheStats,
+    MirroredRegistry, PluginSnapshot, PluginSource, RegistryBackend,
+};
+
+/// Sidecar file (next to `.version`) holding SHA-256 checksums of the installed files.
+pub const CHECKSUMS_FILE_NAME: &str = ".checksums";
+
+/// Sidecar file (next to `.version`) holding the plugin's [`InstallRecord`].
+pub const INSTALL_RECORD_FILE_NAME: &str = ".install.json";
+
+/// Sidecar file (next to `.version`) counting consecutive load failures.
+pub const LOAD_FAILURES_FILE_NAME: &str = ".load_failures";
+
+/// Consecutive load failures after which a plugin is quarantined, unless configured.
+pub const DEFAULT_QUARANTINE_THRESHOLD: u32 = 3;
+
+/// Suffix of the per-plugin lock file (`<id>.install.lock`) in the install directory.
+///
+/// The file itself is left in place; the lock is an OS advisory lock on it.
+pub const INSTALL_LOCK_SUFFIX: &str = ".install.lock";
+
+/// How long to wait for another installer to release a plugin's lock.
+pub const DEFAULT_INSTALL_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Result of a successful plugin installation.
 #[derive(Debug, Clone)]
@@ -16,6 +46,10 @@ pub struct InstallResult {
     pub id: String,
     pub version: String,
     pub path: PathBuf,
+    /// Size of the downloaded archive in bytes
+    pub bytes: u64,
+    /// Provenance written next to `.version`: source, checksum, verification
+    pub record: InstallRecord,
 }
 
 /// Result of an update check.
@@ -31,21 +65,68 @@ pub enum UpdateCheck {
 ///
 /// Contains no UI logic. Callers handle progress bars, i18n messages, and prompts.
 pub struct PluginInstaller {
-    client: RegistryClient,
+    client: Box<dyn RegistryBackend>,
+    registry_url: String,
     install_dir: PathBuf,
+    system_dir: Option<PathBuf>,
+    manifest_cache: ManifestCache,
+    load_order_cache: Mutex<Option<CachedLoadOrder>>,
+    allow_downgrade: bool,
+    quarantine_threshold: u32,
+    lock_timeout: Duration,
+    allowlist: Option<Vec<String>>,
+    blocklist: Vec<String>,
+    binary_resolver: crate::BinaryResolver,
+}
+
+/// Load order computed for a specific set of installed `(id, version)` pairs.
+struct CachedLoadOrder {
+    installed: Vec<(String, String)>,
+    manifests: Vec<Option<(SystemTime, u64)>>,
+    order: Vec<String>,
 }
 
 impl PluginInstaller {
-    /// Create an installer from a `PluginConfig`.
+    /// Create an installer from a `PluginConfig`, validating it first.
+    ///
+    /// Fails with [`HostError::InvalidConfig`] listing every problem found by
+    /// [`PluginConfig::validate`](crate::PluginConfig::validate), so a bad
+    /// config is reported at startup rather than at first install.
+    pub fn try_from_config(config: &crate::PluginConfig) -> Result<Self, HostError> {
+        config.validate()?;
+        Ok(Self::from_config(config))
+    }
+
+    /// Create an installer from a `PluginConfig` without validating it.
+    ///
+    /// If mirrors are configured, requests fall back through them in order.
+    /// Prefer [`Self::try_from_config`] unless the config was already validated.
     pub fn from_config(config: &crate::PluginConfig) -> Self {
         let url = config
             .registry_url
             .as_deref()
             .unwrap_or("https://registry.example.com");
-        let client = RegistryClient::new(url).with_cache(config.cache_dir.clone());
+        let cache_dir = crate::expand_path(&config.cache_dir);
+        let client: Box<dyn RegistryBackend> = if config.registry_mirrors.is_empty() {
+            Box::new(RegistryClient::new(url).with_cache(cache_dir))
+        } else {
+            let mut urls = vec![url.to_string()];
+            urls.extend(config.registry_mirrors.iter().cloned());
+            Box::new(MirroredRegistry::from_urls(&urls, &cache_dir))
+        };
         Self {
             client,
-            install_dir: config.plugins_dir.clone(),
+            registry_url: url.to_string(),
+            install_dir: crate::expand_path(&config.plugins_dir),
+            system_dir: config.system_plugins_dir.as_deref().map(crate::expand_path),
+            manifest_cache: ManifestCache::new().with_max_size(config.max_manifest_size),
+            load_order_cache: Mutex::new(None),
+            allow_downgrade: config.allow_downgrade,
+            quarantine_threshold: config.quarantine_threshold,
+            lock_timeout: DEFAULT_INSTALL_LOCK_TIMEOUT,
+            allowlist: config.allowlist.clone(),
+            blocklist: config.blocklist.clone(),
+            binary_resolver: config.binary_resolver.clone(),
         }
     }
 
@@ -53,8 +134,66 @@ impl PluginInstaller {
     pub fn new(registry_url: &str, install_dir: PathBuf, cache_dir: PathBuf) -> Self {
         let client = RegistryClient::new(registry_url).with_cache(cache_dir);
         Self {
-            client,
+            client: Box::new(client),
+            registry_url: registry_url.to_string(),
             install_dir,
+            system_dir: None,
+            manifest_cache: ManifestCache::new().with_max_size(crate::DEFAULT_MAX_MANIFEST_SIZE),
+            load_order_cache: Mutex::new(None),
+            allow_downgrade: false,
+            quarantine_threshold: DEFAULT_QUARANTINE_THRESHOLD,
+            lock_timeout: DEFAULT_INSTALL_LOCK_TIMEOUT,
+            allowlist: None,
+            blocklist: Vec::new(),
+            binary_resolver: crate::BinaryResolver::default(),
+        }
+    }
+
+    /// Allow installs that replace a newer version with an older one.
+    pub fn allow_downgrade(mut self, allow: bool) -> Self {
+        self.allow_downgrade = allow;
+        self
+    }
+
+    /// Locate plugin binaries with `resolver` when listing, validating and loading.
+    pub fn with_binary_resolver(mut self, resolver: crate::BinaryResolver) -> Self {
+        self.binary_resolver = resolver;
+        self
+    }
+
+    /// How plugin binaries are located inside their version directories.
+    pub fn binary_resolver(&self) -> &crate::BinaryResolver {
+        &self.binary_resolver
+    }
+
+    /// Set how long install and uninstall wait for another process holding the plugin's lock.
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Create from a `PluginConfig` with a custom registry backend.
+    ///
+    /// Useful for offline/filesystem registries and for injecting a mock in tests.
+    /// The config is not validated; call [`PluginConfig::validate`](crate::PluginConfig::validate)
+    /// first if it comes from user input.
+    pub fn with_registry_backend(
+        config: &crate::PluginConfig,
+        backend: Box<dyn RegistryBackend>,
+    ) -> Self {
+        Self {
+            client: backend,
+            registry_url: config.registry_url.clone().unwrap_or_default(),
+            install_dir: crate::expand_path(&config.plugins_dir),
+            system_dir: config.system_plugins_dir.as_deref().map(crate::expand_path),
+            manifest_cache: ManifestCache::new().with_max_size(config.max_manifest_size),
+            load_order_cache: Mutex::new(None),
+            allow_downgrade: config.allow_downgrade,
+            quarantine_threshold: config.quarantine_threshold,
+            lock_timeout: DEFAULT_INSTALL_LOCK_TIMEOUT,
+            allowlist: config.allowlist.clone(),
+            blocklist: config.blocklist.clone(),
+            binary_resolver: config.binary_resolver.clone(),
         }
     }
 
@@ -63,21 +202,64 @@ impl PluginInstaller {
         &self.install_dir
     }
 
-    /// Path to a specific plugin's root directory.
+    /// Path to a specific plugin's root directory in the writable install directory.
     pub fn plugin_path(&self, id: &str) -> PathBuf {
         self.install_dir.join(id)
     }
 
+    /// Which plugins directory an installed plugin is used from.
+    ///
+    /// A plugin in the user directory shadows one with the same ID in the
+    /// system directory.
+    pub fn plugin_source(&self, id: &str) -> Option<PluginSource> {
+        if self.install_dir.join(id).join(".version").exists() {
+            return Some(PluginSource::User);
+        }
+        self.system_dir
+            .as_ref()
+            .filter(|dir| dir.join(id).join(".version").exists())
+            .map(|_| PluginSource::System)
+    }
+
+    /// Check a plugin ID against the configured allowlist and blocklist.
+    ///
+    /// The blocklist wins over the allowlist.
+    pub fn is_permitted(&self, id: &str) -> bool {
+        let allowed = self
+            .allowlist
+            .as_ref()
+            .is_none_or(|patterns| patterns.iter().any(|p| matches_glob(id, p)));
+        allowed && !self.blocklist.iter().any(|p| matches_glob(id, p))
+    }
+
+    /// Plugins directory a plugin is used from: the user directory, else the system one.
+    fn plugins_dir_for(&self, id: &str) -> &Path {
+        match (self.plugin_source(id), &self.system_dir) {
+            (Some(PluginSource::System), Some(system_dir)) => system_dir,
+            _ => &self.install_dir,
+        }
+    }
+
+    /// Root directory a plugin is used from: the user directory, else the system one.
+    fn plugin_root(&self, id: &str) -> PathBuf {
+        self.plugins_dir_for(id).join(id)
+    }
+
+    /// Hit/miss counters of the installed-manifest cache.
+    pub fn manifest_cache_stats(&self) -> ManifestCacheStats {
+        self.manifest_cache.stats()
+    }
+
     // -- Registry operations --
 
     /// Search the plugin registry.
     pub async fn search(&self, query: &str) -> Result<SearchResults, HostError> {
-        Ok(self.client.search(query, SearchKind::All).await?)
+        self.client.search(query).await
     }
 
     /// List all available plugins in the registry.
     pub async fn list_available(&self) -> Result<Vec<PluginEntry>, HostError> {
-        Ok(self.client.list_plugins().await?)
+        self.client.list_plugins().await
     }
 
     /// Check if a plugin exists in the registry (without downloading).
@@ -86,8 +268,8 @@ impl PluginInstaller {
     pub async fn get_plugin_info(&self, id: &str) -> Result<Option<PluginInfo>, HostError> {
         match self.client.get_plugin_latest(id).await {
             Ok(info) => Ok(Some(info)),
-            Err(registry_client::RegistryError::NotFound(_)) => Ok(None),
-            Err(e) => Err(e.into()),
+            Err(HostError::Registry(registry_client::RegistryError::NotFound(_))) => Ok(None),
+            Err(e) => Err(e),
         }
     }
 
@@ -95,36 +277,108 @@ impl PluginInstaller {
 
     /// Check if a plugin is installed. Returns the version string if installed.
     pub fn is_installed(&self, id: &str) -> Option<String> {
-        let version_file = self.install_dir.join(id).join(".version");
+        let version_file = self.plugin_root(id).join(".version");
         std::fs::read_to_string(version_file)
             .ok()
             .map(|v| v.trim().to_string())
     }
 
+    /// Check that an installed plugin's `.version` points at a populated version directory.
+    ///
+    /// Returns the version, or [`HostError::DanglingVersion`] if the directory is
+    /// missing or empty (typically left behind by an interrupted upgrade).
+    pub fn check_version_pointer(&self, id: &str) -> Result<String, HostError> {
+        let version = self
+            .is_installed(id)
+            .ok_or_else(|| HostError::NotInstalled(id.to_string()))?;
+
+        if is_populated_dir(&self.plugin_root(id).join(&version)) {
+            Ok(version)
+        } else {
+            Err(HostError::DanglingVersion {
+                id: id.to_string(),
+                version,
+            })
+        }
+    }
+
+    /// Repair a dangling `.version` pointer by falling back to the highest
+    /// version directory still present.
+    ///
+    /// Rewrites `.version` and the `latest` symlink. Returns the version now in use,
+    /// or [`HostError::DanglingVersion`] if no usable version directory exists.
+    pub fn repair_version_pointer(&self, id: &str) -> Result<String, HostError> {
+        let version = match self.check_version_pointer(id) {
+            Err(HostError::DanglingVersion { version, .. }) => version,
+            other => return other,
+        };
+        if self.plugin_source(id) == Some(PluginSource::System) {
+            return Err(HostError::ReadOnlyPlugin(id.to_string()));
+        }
+
+        let plugin_dir = self.install_dir.join(id);
+        let fallback =
+            highest_version_dir(&plugin_dir).ok_or_else(|| HostError::DanglingVersion {
+                id: id.to_string(),
+                version: version.clone(),
+            })?;
+
+        std::fs::write(plugin_dir.join(".version"), fallback.as_bytes())?;
+        self.invalidate_load_order();
+        if let Err(e) = crate::command_index::update_latest_link(&self.install_dir, id, &fallback) {
+            tracing::warn!(plugin_id = %id, error = %e, "Failed to update latest symlink");
+        }
+
+        tracing::warn!(
+            plugin_id = %id,
+            missing = %version,
+            fallback = %fallback,
+            "Repaired dangling version pointer"
+        );
+        Ok(fallback)
+    }
+
+    /// Read the provenance record written when a plugin was installed.
+    ///
+    /// Returns `None` if the plugin isn't installed or predates install records.
+    pub fn install_record(&self, id: &str) -> Option<InstallRecord> {
+        let path = self.plugin_root(id).join(INSTALL_RECORD_FILE_NAME);
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write_install_record(&self, record: &InstallRecord) -> Result<(), HostError> {
+        let path = self
+            .install_dir
+            .join(&record.id)
+            .join(INSTALL_RECORD_FILE_NAME);
+        let content = serde_json::to_string_pretty(record)
+            .map_err(|e| HostError::Io(std::io::Error::other(e)))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
     /// List all installed plugins as `(id, version)` pairs.
+    ///
+    /// Plugins whose `.version` points at a missing or empty directory are
+    /// skipped with a warning; see [`Self::repair_version_pointer`].
+    /// Includes the system plugins directory, where user plugins shadow system
+    /// ones with the same ID. Plugins not permitted by the allowlist or
+    /// blocklist are hidden. The result is sorted by plugin ID.
     pub async fn list_installed(&self) -> Result<Vec<(String, String)>, HostError> {
-        let mut installed = Vec::new();
-        if !self.install_dir.exists() {
-            return Ok(installed);
-        }
+        self.manifest_cache.prune();
 
-        let mut entries = tokio::fs::read_dir(&self.install_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if path.is_dir() {
-                // Skip the command index directory
-                if entry.file_name() == crate::command_index::COMMANDS_DIR_NAME {
-                    continue;
-                }
-                let version_file = path.join(".version");
-                if version_file.exists() {
-                    let version = tokio::fs::read_to_string(&version_file).await?;
-                    let name = path.file_name().unwrap().to_string_lossy().to_string();
-                    installed.push((name, version.trim().to_string()));
+        let mut installed = scan_installed(&self.install_dir, "plugins_dir").await?;
+        if let Some(system_dir) = &self.system_dir {
+            for (id, version) in scan_installed(system_dir, "system_plugins_dir").await? {
+                if !installed.iter().any(|(user_id, _)| *user_id == id) {
+                    installed.push((id, version));
                 }
             }
         }
 
+        installed.retain(|(id, _)| self.is_permitted(id));
+        installed.sort();
         Ok(installed)
     }
 
@@ -136,12 +390,94 @@ impl PluginInstaller {
     /// writes a `.version` file, and sets executable permissions on Unix.
     ///
     /// `on_progress` is called with `(bytes_done, bytes_total)` during download.
+    ///
+    /// Returns `HostError::DowngradeBlocked` if the resolved version is older than
+    /// the installed one, unless downgrades are allowed; see [`Self::downgrade`].
     pub async fn install(
         &self,
         id: &str,
         version: Option<&str>,
-        on_progress: impl Fn(u64, u64),
+        on_progress: impl Fn(u64, u64) + Send + Sync,
+    ) -> Result<InstallResult, HostError> {
+        self.install_inner(
+            id,
+            version,
+            self.allow_downgrade,
+            None,
+            downloads_only(on_progress),
+        )
+        .instrument(operation_span("install", id))
+        .await
+    }
+
+    /// Install a single plugin, reporting each [`InstallPhase`] as it happens.
+    ///
+    /// `on_status` receives `InstallStatus::Installing` updates with progress
+    /// restarting at 0.0 for every phase, then `Installed` or `Failed`.
+    pub async fn install_with_status(
+        &self,
+        id: &str,
+        version: Option<&str>,
+        on_status: impl Fn(InstallStatus) + Send + Sync,
+    ) -> Result<InstallResult, HostError> {
+        let result = self
+            .install_inner(
+                id,
+                version,
+                self.allow_downgrade,
+                None,
+                |phase, done, total| {
+                    let progress = if total == 0 {
+                        0.0
+                    } else {
+                        (done as f64 / total as f64).min(1.0) as f32
+                    };
+                    on_status(InstallStatus::Installing { phase, progress });
+                },
+            )
+            .instrument(operation_span("install", id))
+            .await;
+
+        match &result {
+            Ok(r) => on_status(InstallStatus::Installed {
+                version: r.version.clone(),
+            }),
+            Err(e) => on_status(InstallStatus::Failed {
+                error: e.to_string(),
+            }),
+        }
+        result
+    }
+
+    async fn install_inner(
+        &self,
+        id: &str,
+        version: Option<&str>,
+        allow_downgrade: bool,
+        expected_checksum: Option<&str>,
+        on_phase: impl Fn(InstallPhase, u64, u64) + Send + Sync,
+    ) -> Result<InstallResult, HostError> {
+        let _lock = InstallLock::acquire(&self.install_dir, id, self.lock_timeout).await?;
+        self.install_unlocked(id, version, allow_downgrade, expected_checksum, on_phase)
+            .await
+    }
+
+    /// Install without taking the plugin's lock; the caller must hold it.
+    async fn install_unlocked(
+        &self,
+        id: &str,
+        version: Option<&str>,
+        allow_downgrade: bool,
+        expected_checksum: Option<&str>,
+        on_phase: impl Fn(InstallPhase, u64, u64) + Send + Sync,
     ) -> Result<InstallResult, HostError> {
+        if !self.is_permitted(id) {
+            return Err(HostError::PluginNotPermitted(id.to_string()));
+        }
+        // An explicit version can be refused before contacting the registry.
+        if let Some(v) = version.filter(|_| !allow_downgrade) {
+            self.check_downgrade(id, v)?;
+        }
         let platform = lib_plugin_manifest::current_platform();
 
         let info = if let Some(v) = version {
@@ -150,6 +486,10 @@ impl PluginInstaller {
             self.client.get_plugin_latest(id).await?
         };
 
+        if !allow_downgrade {
+            self.check_downgrade(id, &info.version)?;
+        }
+
         // Verify platform support
         info.platforms
             .iter()
@@ -162,29 +502,69 @@ impl PluginInstaller {
             })?;
 
         // Download
-        let bytes = self
+        let download = self
             .client
-            .download_plugin(id, &info.version, &platform, |done, total| {
-                on_progress(done, total);
+            .download_plugin(id, &info.version, &platform, &|done, total| {
+                on_phase(InstallPhase::Downloading, done, total)
             })
             .await?;
+        let bytes = download.bytes;
+
+        on_phase(InstallPhase::Verifying, 0, 1);
+        let checksum = sha256_hex(&bytes);
+        for expected in [expected_checksum, download.checksum.as_deref()]
+            .into_iter()
+            .flatten()
+        {
+            check_checksum(id, &checksum, expected)?;
+        }
 
-        // Extract tarball
+        on_phase(InstallPhase::Verifying, 1, 1);
+
+        // Extract tarball (the recorded checksum covers the bytes as delivered)
+        on_phase(InstallPhase::Extracting, 0, 1);
+        let decoder = decompress(&bytes)?;
         let plugin_dir = self.install_dir.join(id).join(&info.version);
         tokio::fs::create_dir_all(&plugin_dir).await?;
 
-        let decoder = flate2::read::GzDecoder::new(&bytes[..]);
         let mut archive = tar::Archive::new(decoder);
         archive.unpack(&plugin_dir)?;
+        on_phase(InstallPhase::Extracting, 1, 1);
 
         // Write version file
+        on_phase(InstallPhase::Finalizing, 0, 1);
         let version_file = self.install_dir.join(id).join(".version");
         tokio::fs::write(&version_file, info.version.as_bytes()).await?;
+        self.invalidate_load_order();
 
         // Set executable permissions on Unix
         #[cfg(unix)]
         set_unix_permissions(&plugin_dir).await;
 
+        // Record checksums for later integrity verification
+        write_checksums(
+            &plugin_dir,
+            &self.install_dir.join(id).join(CHECKSUMS_FILE_NAME),
+        )?;
+
+        // Record provenance
+        let record = InstallRecord {
+            id: id.to_string(),
+            version: info.version.clone(),
+            installed_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            source: download.source.unwrap_or_else(|| self.registry_url.clone()),
+            verified: false,
+            checksum,
+        };
+        self.write_install_record(&record)?;
+        let archive_size = bytes.len() as u64;
+
+        // A fresh install gets a clean slate
+        let _ = std::fs::remove_file(self.install_dir.join(id).join(LOAD_FAILURES_FILE_NAME));
+
         // Update latest symlink (points to current version directory)
         if let Err(e) =
             crate::command_index::update_latest_link(&self.install_dir, id, &info.version)
@@ -200,14 +580,40 @@ impl PluginInstaller {
         {
             tracing::warn!(plugin_id = %id, error = %e, "Failed to create command symlinks");
         }
+        on_phase(InstallPhase::Finalizing, 1, 1);
 
         Ok(InstallResult {
             id: id.to_string(),
             version: info.version,
             path: plugin_dir,
+            bytes: archive_size,
+            record,
         })
     }
 
+    /// Blocking wrapper around [`Self::install`] for callers without an async runtime.
+    ///
+    /// Drives the install on a fresh current-thread runtime. Must not be called
+    /// from within a tokio runtime (nesting runtimes panics); that case returns
+    /// `HostError::BlockingInRuntime` instead.
+    #[cfg(feature = "blocking")]
+    pub fn install_blocking(
+        &self,
+        id: &str,
+        version: Option<&str>,
+    ) -> Result<InstallResult, HostError> {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Err(HostError::BlockingInRuntime(format!(
+                "install_blocking({}) must not be called from async code; use install",
+                id
+            )));
+        }
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(self.install(id, version, |_, _| {}))
+    }
+
     /// Install a plugin and all its dependencies (silent — no progress reporting).
     ///
     /// Returns the list of plugins that were actually installed (skips already-installed).
@@ -273,41 +679,171 @@ impl PluginInstaller {
     /// Update an installed plugin to the latest version.
     ///
     /// Returns `Ok(None)` if already at the latest version, `Ok(Some(result))` if updated.
+    /// The install lock is held throughout, and the old version directory is
+    /// removed only once the new version is installed.
     pub async fn update(
         &self,
         id: &str,
-        on_progress: impl Fn(u64, u64),
+        on_progress: impl Fn(u64, u64) + Send + Sync,
     ) -> Result<Option<InstallResult>, HostError> {
-        let current = self
-            .is_installed(id)
-            .ok_or_else(|| HostError::NotInstalled(id.to_string()))?;
+        async {
+            let _lock = InstallLock::acquire(&self.install_dir, id, self.lock_timeout).await?;
+            let current = self
+                .is_installed(id)
+                .ok_or_else(|| HostError::NotInstalled(id.to_string()))?;
+            let source = self.plugin_source(id);
 
-        let latest = self.client.get_plugin_latest(id).await?;
+            let latest = self.client.get_plugin_latest(id).await?;
 
-        if current == latest.version {
-            return Ok(None);
+            if current == latest.version {
+                return Ok(None);
+            }
+
+            let result = self
+                .install_unlocked(
+                    id,
+                    Some(&latest.version),
+                    self.allow_downgrade,
+                    None,
+                    downloads_only(on_progress),
+                )
+                .await?;
+            if source == Some(PluginSource::User) {
+                self.remove_old_version(id, &current, &result.version)
+                    .await?;
+            }
+            Ok(Some(result))
         }
+        .instrument(operation_span("update", id))
+        .await
+    }
+
+    /// Explicitly install an older version of an installed plugin.
+    ///
+    /// Bypasses the downgrade check, then removes the previously installed
+    /// version directory so no files of the newer version are left behind.
+    /// The install lock is held throughout.
+    pub async fn downgrade(
+        &self,
+        id: &str,
+        to_version: &str,
+        on_progress: impl Fn(u64, u64) + Send + Sync,
+    ) -> Result<InstallResult, HostError> {
+        async {
+            let _lock = InstallLock::acquire(&self.install_dir, id, self.lock_timeout).await?;
+            let current = self
+                .is_installed(id)
+                .ok_or_else(|| HostError::NotInstalled(id.to_string()))?;
+            let source = self.plugin_source(id);
+
+            let result = self
+                .install_unlocked(
+                    id,
+                    Some(to_version),
+                    true,
+                    None,
+                    downloads_only(on_progress),
+                )
+                .await?;
+            if source == Some(PluginSource::User) {
+                self.remove_old_version(id, &current, &result.version)
+                    .await?;
+            }
+            Ok(result)
+        }
+        .instrument(operation_span("downgrade", id))
+        .await
+    }
 
-        // Remove old version directory
-        // Note: command symlinks don't need removal — they point through latest/
-        // which install() will re-point to the new version.
-        let old_dir = self.install_dir.join(id).join(&current);
-        if old_dir.exists() {
+    /// Delete the version directory an update or downgrade replaced.
+    ///
+    /// Only called for user plugins: a system plugin's old version stays in the
+    /// read-only system directory, shadowed by the new user install. Command
+    /// symlinks need no cleanup: they point through `latest`, which the install
+    /// already re-pointed.
+    async fn remove_old_version(
+        &self,
+        id: &str,
+        old_version: &str,
+        new_version: &str,
+    ) -> Result<(), HostError> {
+        let old_dir = self.install_dir.join(id).join(old_version);
+        if old_version != new_version && old_dir.exists() {
             tokio::fs::remove_dir_all(&old_dir).await?;
         }
+        Ok(())
+    }
 
-        let result = self.install(id, Some(&latest.version), on_progress).await?;
-        Ok(Some(result))
+    /// Fail with `HostError::DowngradeBlocked` if `target` is older than the installed version.
+    fn check_downgrade(&self, id: &str, target: &str) -> Result<(), HostError> {
+        match self.is_installed(id) {
+            Some(current) if compare_versions(target, &current) == Ordering::Less => {
+                Err(HostError::DowngradeBlocked {
+                    id: id.to_string(),
+                    from: current,
+                    to: target.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
     }
 
     // -- Uninstall --
 
     /// Uninstall a plugin by removing its directory.
+    ///
+    /// Returns `HostError::DependencyInUse` if other installed plugins depend on
+    /// it; use [`Self::uninstall_forced`] to remove it anyway.
     pub async fn uninstall(&self, id: &str) -> Result<(), HostError> {
-        let plugin_dir = self.install_dir.join(id);
-        if !plugin_dir.exists() {
-            return Err(HostError::NotInstalled(id.to_string()));
+        self.uninstall_inner(id, false)
+            .instrument(operation_span("uninstall", id))
+            .await
+    }
+
+    /// Uninstall a plugin even if other installed plugins depend on it.
+    pub async fn uninstall_forced(&self, id: &str) -> Result<(), HostError> {
+        self.uninstall_inner(id, true)
+            .instrument(operation_span("uninstall", id))
+            .await
+    }
+
+    async fn uninstall_inner(&self, id: &str, force: bool) -> Result<(), HostError> {
+        let lock = self.begin_uninstall(id, force).await?;
+        self.finish_uninstall(id, lock).await
+    }
+
+    /// Run every check that can refuse an uninstall and take the plugin's install lock.
+    ///
+    /// Nothing is changed yet; pass the lock to [`Self::finish_uninstall`].
+    pub(crate) async fn begin_uninstall(
+        &self,
+        id: &str,
+        force: bool,
+    ) -> Result<InstallLock, HostError> {
+        match self.plugin_source(id) {
+            Some(PluginSource::User) => {}
+            Some(PluginSource::System) => return Err(HostError::ReadOnlyPlugin(id.to_string())),
+            None => return Err(HostError::NotInstalled(id.to_string())),
+        }
+        if !force {
+            let required_by = self.dependents(id).await?;
+            if !required_by.is_empty() {
+                return Err(HostError::DependencyInUse {
+                    package: id.to_string(),
+                    required_by,
+                });
+            }
         }
+        InstallLock::acquire(&self.install_dir, id, self.lock_timeout).await
+    }
+
+    /// Delete a plugin's files while holding the lock from [`Self::begin_uninstall`].
+    pub(crate) async fn finish_uninstall(
+        &self,
+        id: &str,
+        _lock: InstallLock,
+    ) -> Result<(), HostError> {
+        let plugin_dir = self.install_dir.join(id);
 
         // Remove command index symlinks before removing plugin directory
         if let Err(e) = crate::command_index::remove_command_symlinks(&self.install_dir, id) {
@@ -315,106 +851,886 @@ impl PluginInstaller {
         }
 
         tokio::fs::remove_dir_all(&plugin_dir).await?;
+        self.invalidate_load_order();
         Ok(())
     }
 
     // -- Dependencies --
 
+    /// Path to the installed version directory of a plugin (`<id>/<version>`).
+    pub fn version_path(&self, id: &str) -> Option<PathBuf> {
+        self.is_installed(id)
+            .map(|version| self.plugin_root(id).join(version))
+    }
+
+    /// Read the manifest of the installed version of a plugin.
+    ///
+    /// Parsed manifests are cached until the file changes on disk.
+    pub fn installed_manifest(&self, id: &str) -> Result<PluginManifest, HostError> {
+        let version_dir = self
+            .version_path(id)
+            .ok_or_else(|| HostError::NotInstalled(id.to_string()))?;
+        self.manifest_cache.load(&version_dir.join("plugin.toml"))
+    }
+
+    /// Read the unparsed `plugin.toml` of the installed version of a plugin.
+    ///
+    /// For keys [`PluginManifest`] doesn't model yet. Not cached, but subject to
+    /// the same size limit as parsed manifests.
+    pub fn raw_manifest(&self, id: &str) -> Result<String, HostError> {
+        let version_dir = self
+            .version_path(id)
+            .ok_or_else(|| HostError::NotInstalled(id.to_string()))?;
+        self.manifest_cache
+            .read_raw(&version_dir.join("plugin.toml"))
+    }
+
+    /// Find installed plugins whose manifests declare the same plugin ID.
+    ///
+    /// The install directory name normally equals the plugin ID, but a
+    /// hand-copied or misnamed directory can shadow another plugin.
+    pub async fn duplicate_plugin_ids(&self) -> Result<Vec<DuplicatePluginId>, HostError> {
+        let installed = self.list_installed().await?;
+
+        let mut by_id: Vec<DuplicatePluginId> = Vec::new();
+        for (dir_name, _) in installed {
+            let (Ok(manifest), Some(path)) = (
+                self.installed_manifest(&dir_name),
+                self.version_path(&dir_name),
+            ) else {
+                continue;
+            };
+            match by_id.iter_mut().find(|d| d.id == manifest.plugin.id) {
+                Some(entry) => entry.paths.push(path),
+                None => by_id.push(DuplicatePluginId {
+                    id: manifest.plugin.id,
+                    paths: vec![path],
+                }),
+            }
+        }
+
+        by_id.retain(|d| d.paths.len() > 1);
+        Ok(by_id)
+    }
+
     /// Read dependencies from an installed plugin's manifest.
     ///
     /// Uses `PluginManifest` deserialization (not manual TOML parsing).
+    /// Parsed manifests are cached until the file changes on disk.
     pub fn get_dependencies(&self, id: &str) -> Vec<String> {
-        let plugin_dir = self.install_dir.join(id);
-        let version_file = plugin_dir.join(".version");
-
-        let version = match std::fs::read_to_string(version_file) {
-            Ok(v) => v.trim().to_string(),
-            Err(_) => return Vec::new(),
-        };
-
-        let manifest_path = plugin_dir.join(&version).join("plugin.toml");
-        match PluginManifest::from_file(&manifest_path) {
+        match self.installed_manifest(id) {
             Ok(manifest) => manifest.compatibility.depends_on,
             Err(_) => Vec::new(),
         }
     }
 
-    // -- Pattern matching --
-
-    /// Find all available plugins matching a glob pattern (e.g., "adi.lang.*").
-    pub async fn find_matching(&self, pattern: &str) -> Result<Vec<PluginEntry>, HostError> {
-        let all = self.list_available().await?;
-        Ok(all
+    /// Installed plugins that list `id` in their `depends_on`, sorted by ID.
+    pub async fn dependents(&self, id: &str) -> Result<Vec<String>, HostError> {
+        Ok(self
+            .list_installed()
+            .await?
             .into_iter()
-            .filter(|p| matches_glob(&p.id, pattern))
+            .map(|(other, _)| other)
+            .filter(|other| other != id && self.get_dependencies(other).iter().any(|d| d == id))
             .collect())
     }
-}
-
-/// Set executable permissions on non-text files in a directory (Unix only).
-#[cfg(unix)]
-async fn set_unix_permissions(dir: &PathBuf) {
-    use std::os::unix::fs::PermissionsExt;
 
-    let mut entries = match tokio::fs::read_dir(dir).await {
-        Ok(e) => e,
-        Err(_) => return,
-    };
+    /// Order all installed plugins so every plugin comes after its dependencies.
+    ///
+    /// Dependencies that aren't installed are left out of the order. Returns
+    /// [`HostError::DependencyCycle`] if the installed plugins depend on each other in a loop.
+    ///
+    /// The order is cached and reused while the installed `(id, version)` set
+    /// and the modification time and size of every manifest are unchanged;
+    /// installs and uninstalls through this installer invalidate it.
+    pub async fn installed_load_order(&self) -> Result<Vec<String>, HostError> {
+        let installed = self.list_installed().await?;
+        let manifests: Vec<_> = installed
+            .iter()
+            .map(|(id, _)| self.manifest_stamp(id))
+            .collect();
 
-    while let Ok(Some(entry)) = entries.next_entry().await {
-        let path = entry.path();
-        if path.is_file() {
-            if let Ok(metadata) = tokio::fs::metadata(&path).await {
-                let mut perms = metadata.permissions();
-                if !path
-                    .extension()
-                    .is_some_and(|e| e == "json" || e == "toml" || e == "txt" || e == "md")
-                {
-                    perms.set_mode(0o755);
-                    let _ = tokio::fs::set_permissions(&path, perms).await;
-                }
+        if let Some(cached) = self.load_order_cache.lock().unwrap().as_ref() {
+            if cached.installed == installed && cached.manifests == manifests {
+                return Ok(cached.order.clone());
             }
         }
-    }
-}
 
-/// Check if a string contains glob wildcards.
-pub fn is_glob_pattern(s: &str) -> bool {
-    s.contains('*')
-}
+        let ids: Vec<String> = installed.iter().map(|(id, _)| id.clone()).collect();
+        let installed_ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        let order = resolve_load_order(&ids, |id| {
+            self.get_dependencies(id)
+                .into_iter()
+                .filter(|dep| installed_ids.contains(dep.as_str()))
+                .collect()
+        })?;
+
+        *self.load_order_cache.lock().unwrap() = Some(CachedLoadOrder {
+            installed,
+            manifests,
+            order: order.clone(),
+        });
+        Ok(order)
+    }
 
-/// Match a string against a simple glob pattern (supports `*` wildcard).
-pub fn matches_glob(s: &str, pattern: &str) -> bool {
-    let parts: Vec<&str> = pattern.split('*').collect();
+    /// Modification time and size of a plugin's installed manifest.
+    fn manifest_stamp(&self, id: &str) -> Option<(SystemTime, u64)> {
+        let metadata = std::fs::metadata(self.version_path(id)?.join("plugin.toml")).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
 
-    if parts.len() == 1 {
-        return s == pattern;
+    /// Forget the cached load order after the installed set changed.
+    fn invalidate_load_order(&self) {
+        *self.load_order_cache.lock().unwrap() = None;
     }
 
-    let mut pos = 0;
-    for (i, part) in parts.iter().enumerate() {
-        if i == 0 {
-            if !s.starts_with(part) {
-                return false;
-            }
-            pos = part.len();
-        } else if i == parts.len() - 1 {
-            if !s.ends_with(part) {
-                return false;
-            }
-        } else if let Some(found_pos) = s[pos..].find(part) {
-            pos += found_pos + part.len();
-        } else {
-            return false;
+    // -- Local queries --
+
+    /// All installed plugins with their manifests, without contacting the registry.
+    ///
+    /// Plugins whose manifest cannot be read are skipped. The installer has no
+    /// notion of disabled plugins, so `enabled` is always `true`.
+    pub async fn installed_plugins(&self) -> Result<Vec<InstalledPlugin>, HostError> {
+        let installed = self.list_installed().await?;
+
+        let mut plugins = Vec::new();
+        for (dir_name, version) in installed {
+            let manifest = match self.installed_manifest(&dir_name) {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    tracing::warn!(plugin_id = %dir_name, error = %e, "Skipping plugin with unreadable manifest");
+                    continue;
+                }
+            };
+            let version_dir = self.plugin_root(&dir_name).join(&version);
+            let path = self
+                .binary_resolver
+                .resolve(&manifest, &version_dir)
+                .unwrap_or(version_dir);
+            plugins.push(InstalledPlugin {
+                manifest,
+                path,
+                package_id: dir_name,
+                enabled: true,
+            });
         }
+        Ok(plugins)
     }
 
-    true
-}
+    /// Installed plugins matching `predicate`, ordered by install directory name.
+    pub async fn find_installed(
+        &self,
+        predicate: impl Fn(&InstalledPlugin) -> bool,
+    ) -> Result<Vec<InstalledPlugin>, HostError> {
+        let mut plugins = self.installed_plugins().await?;
+        plugins.retain(|p| predicate(p));
+        Ok(plugins)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Installed plugins whose manifest declares the given `type`.
+    pub async fn installed_by_type(
+        &self,
+        plugin_type: &str,
+    ) -> Result<Vec<InstalledPlugin>, HostError> {
+        self.find_installed(|p| p.plugin_type() == plugin_type)
+            .await
+    }
+
+    /// Installed plugins that provide the given service ID.
+    pub async fn installed_providing(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<InstalledPlugin>, HostError> {
+        self.find_installed(|p| p.manifest.provides.iter().any(|s| s.id == service_id))
+            .await
+    }
+
+    // -- Disk usage --
+
+    /// Total size in bytes of all files under a plugin's directory, every version included.
+    ///
+    /// Not cached: a key that catches every change would need the size of every
+    /// file, which is the whole cost of computing the total.
+    pub fn package_size(&self, id: &str) -> Result<u64, HostError> {
+        let plugin_dir = self.plugin_root(id);
+        if !plugin_dir.is_dir() {
+            return Err(HostError::NotInstalled(id.to_string()));
+        }
+
+        let mut size = 0;
+        for file in list_files(&plugin_dir)? {
+            size += std::fs::metadata(plugin_dir.join(file))?.len();
+        }
+        Ok(size)
+    }
+
+    /// Every file under a plugin's directory, relative to it and sorted.
+    ///
+    /// Covers all version directories plus `.version` and the other sidecar
+    /// files, i.e. exactly what [`Self::uninstall`] deletes.
+    pub fn package_files(&self, id: &str) -> Result<Vec<PathBuf>, HostError> {
+        let plugin_dir = self.plugin_root(id);
+        if !plugin_dir.is_dir() {
+            return Err(HostError::NotInstalled(id.to_string()));
+        }
+        Ok(list_files(&plugin_dir)?)
+    }
+
+    /// Sum of [`Self::package_size`] over every plugin directory.
+    pub fn total_installed_size(&self) -> Result<u64, HostError> {
+        if !self.install_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut total = 0;
+        for entry in std::fs::read_dir(&self.install_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir()
+                || entry.file_name() == crate::command_index::COMMANDS_DIR_NAME
+            {
+                continue;
+            }
+            if let Some(id) = entry.file_name().to_str() {
+                total += self.package_size(id)?;
+            }
+        }
+        Ok(total)
+    }
+
+    // -- Quarantine --
+    //
+    // Failure counts always live in the writable install directory, so system
+    // plugins can be quarantined too.
+
+    /// Consecutive load failures recorded for a plugin.
+    pub fn load_failures(&self, id: &str) -> u32 {
+        std::fs::read_to_string(self.install_dir.join(id).join(LOAD_FAILURES_FILE_NAME))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Record a failed load. Returns the new consecutive failure count.
+    pub fn record_load_failure(&self, id: &str) -> Result<u32, HostError> {
+        if self.plugin_source(id).is_none() {
+            return Err(HostError::NotInstalled(id.to_string()));
+        }
+        let plugin_dir = self.install_dir.join(id);
+        std::fs::create_dir_all(&plugin_dir)?;
+        let failures = self.load_failures(id) + 1;
+        std::fs::write(
+            plugin_dir.join(LOAD_FAILURES_FILE_NAME),
+            failures.to_string(),
+        )?;
+        if failures == self.quarantine_threshold {
+            tracing::warn!(plugin_id = %id, failures, "Plugin quarantined");
+        }
+        Ok(failures)
+    }
+
+    /// Record a successful load, resetting the failure count.
+    pub fn record_load_success(&self, id: &str) {
+        let _ = std::fs::remove_file(self.install_dir.join(id).join(LOAD_FAILURES_FILE_NAME));
+    }
+
+    /// Check if a plugin failed to load too many times in a row to be auto-loaded.
+    pub fn is_quarantined(&self, id: &str) -> bool {
+        self.load_failures(id) >= self.quarantine_threshold
+    }
+
+    /// IDs of all quarantined plugins, sorted.
+    pub fn quarantined(&self) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.install_dir) else {
+            return Vec::new();
+        };
+        let mut ids: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|id| self.is_quarantined(id))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Lift a plugin's quarantine so it is auto-loaded again.
+    pub fn clear_quarantine(&self, id: &str) {
+        self.record_load_success(id);
+    }
+
+    // -- Integrity --
+
+    /// Verify an installed plugin's files against the checksums recorded at install time.
+    ///
+    /// Returns [`HostError::IntegrityMismatch`] listing every file that changed or
+    /// went missing. Plugins installed without a checksum sidecar fail with an IO error.
+    pub fn verify_installed(&self, id: &str) -> Result<(), HostError> {
+        let version = self
+            .is_installed(id)
+            .ok_or_else(|| HostError::NotInstalled(id.to_string()))?;
+        let plugin_dir = self.plugin_root(id);
+        let expected = std::fs::read_to_string(plugin_dir.join(CHECKSUMS_FILE_NAME))?;
+        let version_dir = plugin_dir.join(&version);
+
+        let mut mismatched = Vec::new();
+        for line in expected.lines() {
+            let Some((checksum, file)) = line.split_once("  ") else {
+                continue;
+            };
+            match sha256_file(&version_dir.join(file)) {
+                Ok(actual) if actual == checksum => {}
+                _ => mismatched.push(file.to_string()),
+            }
+        }
+
+        if mismatched.is_empty() {
+            Ok(())
+        } else {
+            Err(HostError::IntegrityMismatch {
+                id: id.to_string(),
+                files: mismatched,
+            })
+        }
+    }
+
+    // -- State export/import --
+
+    /// Snapshot every installed plugin's version and `config.json`.
+    pub async fn export_state(&self) -> Result<HostStateSnapshot, HostError> {
+        let installed = self.list_installed().await?;
+
+        let mut plugins = Vec::with_capacity(installed.len());
+        for (id, version) in installed {
+            let config_path = crate::loader_v3::plugin_config_dir(&id)?.join("config.json");
+            let config = match std::fs::read_to_string(&config_path) {
+                Ok(content) => Some(
+                    serde_json::from_str(&content)
+                        .map_err(|e| HostError::Io(std::io::Error::other(e)))?,
+                ),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+            plugins.push(PluginSnapshot {
+                id,
+                version,
+                config,
+            });
+        }
+
+        Ok(HostStateSnapshot { plugins })
+    }
+
+    /// Restore a snapshot: install any plugin not at its snapshot version and
+    /// write back each plugin's `config.json`.
+    ///
+    /// Snapshot versions older than the installed ones are restored regardless
+    /// of the downgrade policy. Plugins installed but absent from the snapshot
+    /// are left alone. Failures are collected per plugin rather than aborting
+    /// the import.
+    pub async fn import_state(&self, snapshot: &HostStateSnapshot) -> ImportReport {
+        let mut report = ImportReport::default();
+
+        for plugin in &snapshot.plugins {
+            if self.is_installed(&plugin.id).as_deref() != Some(plugin.version.as_str()) {
+                match self
+                    .install_inner(&plugin.id, Some(&plugin.version), true, None, |_, _, _| {})
+                    .instrument(operation_span("install", &plugin.id))
+                    .await
+                {
+                    Ok(_) => report.installed.push(plugin.id.clone()),
+                    Err(e) => {
+                        report.failed.push((plugin.id.clone(), e));
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(config) = &plugin.config {
+                if let Err(e) = write_plugin_config(&plugin.id, config) {
+                    report.failed.push((plugin.id.clone(), e));
+                }
+            }
+        }
+
+        report
+    }
+
+    // -- Lockfile --
+
+    /// Write a lockfile pinning every installed plugin's version and archive checksum.
+    ///
+    /// Fails if a plugin has no install record (installed before records were
+    /// kept); reinstall it to lock it.
+    pub async fn write_lockfile(&self, path: &Path) -> Result<Lockfile, HostError> {
+        let mut lockfile = Lockfile::default();
+        for (id, version) in self.list_installed().await? {
+            let record = self
+                .install_record(&id)
+                .filter(|r| r.version == version)
+                .ok_or_else(|| {
+                    HostError::Io(std::io::Error::other(format!(
+                        "{} {} has no install record to lock",
+                        id, version
+                    )))
+                })?;
+            lockfile.plugins.push(LockedPlugin {
+                id,
+                version,
+                checksum: record.checksum,
+            });
+        }
+
+        let content = serde_json::to_string_pretty(&lockfile)
+            .map_err(|e| HostError::Io(std::io::Error::other(e)))?;
+        std::fs::write(path, content)?;
+        Ok(lockfile)
+    }
+
+    /// Install exactly the versions pinned in a lockfile.
+    ///
+    /// Plugins already installed at the locked version with a matching checksum
+    /// are skipped. Anything else is downloaded and must match the locked
+    /// checksum, or installation stops with `HostError::ChecksumMismatch`.
+    /// Returns the plugins that were installed.
+    pub async fn install_from_lockfile(
+        &self,
+        path: &Path,
+    ) -> Result<Vec<InstallResult>, HostError> {
+        let content = std::fs::read_to_string(path)?;
+        let lockfile: Lockfile =
+            serde_json::from_str(&content).map_err(|e| HostError::Io(std::io::Error::other(e)))?;
+
+        let mut results = Vec::new();
+        for locked in &lockfile.plugins {
+            let up_to_date = self
+                .install_record(&locked.id)
+                .is_some_and(|r| r.version == locked.version && r.checksum == locked.checksum)
+                && self.is_installed(&locked.id).as_deref() == Some(locked.version.as_str());
+            if up_to_date {
+                continue;
+            }
+
+            let result = self
+                .install_inner(
+                    &locked.id,
+                    Some(&locked.version),
+                    true,
+                    Some(&locked.checksum),
+                    |_, _, _| {},
+                )
+                .await?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    // -- Validation --
+
+    /// Validate an installed plugin without loading its binary.
+    ///
+    /// See [`crate::validate_installed_plugin`] for the checks performed.
+    pub fn validate(&self, id: &str) -> Vec<crate::ValidationIssue> {
+        crate::validation::validate_with_resolver(
+            self.plugins_dir_for(id),
+            id,
+            &self.binary_resolver,
+            self.manifest_cache.max_size(),
+            |dep| self.is_installed(dep).is_some(),
+        )
+    }
+
+    // -- Pattern matching --
+
+    /// Find all available plugins matching a glob pattern (e.g., "adi.lang.*").
+    pub async fn find_matching(&self, pattern: &str) -> Result<Vec<PluginEntry>, HostError> {
+        let all = self.list_available().await?;
+        Ok(all
+            .into_iter()
+            .filter(|p| matches_glob(&p.id, pattern))
+            .collect())
+    }
+}
+
+/// Read `(id, version)` for every plugin directory under `dir`.
+///
+/// Directory names that aren't valid UTF-8 can't be plugin IDs and are skipped
+/// with a warning rather than lossily renamed. A `dir` that exists but isn't a
+/// directory is reported as [`HostError::InvalidConfig`] naming `setting`.
+async fn scan_installed(dir: &Path, setting: &str) -> Result<Vec<(String, String)>, HostError> {
+    let mut installed = Vec::new();
+    if !dir.exists() {
+        return Ok(installed);
+    }
+    if !dir.is_dir() {
+        return Err(HostError::InvalidConfig(vec![format!(
+            "{} is not a directory: {}",
+            setting,
+            dir.display()
+        )]));
+    }
+
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        // Skip the command index directory
+        if !path.is_dir() || entry.file_name() == crate::command_index::COMMANDS_DIR_NAME {
+            continue;
+        }
+        let Ok(name) = entry.file_name().into_string() else {
+            tracing::warn!(path = %path.display(), "Skipping plugin directory with non-UTF-8 name");
+            continue;
+        };
+        if let Some(pair) = read_installed_version(path, name).await? {
+            installed.push(pair);
+        }
+    }
+
+    Ok(installed)
+}
+
+/// Read `(id, version)` from a plugin directory, or `None` if it has no `.version` file.
+async fn read_installed_version(
+    path: PathBuf,
+    name: String,
+) -> Result<Option<(String, String)>, HostError> {
+    let version_file = path.join(".version");
+    if !version_file.exists() {
+        return Ok(None);
+    }
+    let version = tokio::fs::read_to_string(&version_file).await?;
+    let version = version.trim().to_string();
+
+    if !is_populated_dir(&path.join(&version)) {
+        let e = HostError::DanglingVersion { id: name, version };
+        tracing::warn!(error = %e, "Skipping plugin with dangling version pointer");
+        return Ok(None);
+    }
+
+    Ok(Some((name, version)))
+}
+
+/// Write a plugin's `config.json`, creating its config directory if needed.
+fn write_plugin_config(plugin_id: &str, config: &serde_json::Value) -> Result<(), HostError> {
+    let config_dir = crate::loader_v3::plugin_config_dir(plugin_id)?;
+    std::fs::create_dir_all(&config_dir)?;
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| HostError::Io(std::io::Error::other(e)))?;
+    std::fs::write(config_dir.join("config.json"), content)?;
+    Ok(())
+}
+
+/// Topologically sort `ids` so each comes after its dependencies.
+///
+/// Roots are visited in ID order, so the result is deterministic.
+pub fn resolve_load_order(
+    ids: &[String],
+    dependencies: impl Fn(&str) -> Vec<String>,
+) -> Result<Vec<String>, HostError> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Mark {
+        Visiting,
+        Done,
+    }
+
+    fn visit(
+        id: &str,
+        dependencies: &dyn Fn(&str) -> Vec<String>,
+        marks: &mut HashMap<String, Mark>,
+        stack: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), HostError> {
+        match marks.get(id) {
+            Some(Mark::Done) => return Ok(()),
+            Some(Mark::Visiting) => {
+                let start = stack.iter().position(|s| s == id).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(id.to_string());
+                return Err(HostError::DependencyCycle(cycle));
+            }
+            None => {}
+        }
+
+        marks.insert(id.to_string(), Mark::Visiting);
+        stack.push(id.to_string());
+
+        let mut deps = dependencies(id);
+        deps.sort();
+        for dep in &deps {
+            visit(dep, dependencies, marks, stack, order)?;
+        }
+
+        stack.pop();
+        marks.insert(id.to_string(), Mark::Done);
+        order.push(id.to_string());
+        Ok(())
+    }
+
+    let mut roots = ids.to_vec();
+    roots.sort();
+
+    let mut marks = HashMap::new();
+    let mut stack = Vec::new();
+    let mut order = Vec::new();
+    for id in &roots {
+        visit(id, &dependencies, &mut marks, &mut stack, &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Whether `dir` exists and contains at least one entry.
+fn is_populated_dir(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// Highest populated version directory inside a plugin directory.
+fn highest_version_dir(plugin_dir: &Path) -> Option<String> {
+    std::fs::read_dir(plugin_dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|entry| is_populated_dir(&entry.path()))
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .max_by(|a, b| compare_versions(a, b))
+}
+
+/// Compare two dotted version strings numerically (`0.10.0 > 0.9.1`).
+///
+/// Non-numeric components (e.g. pre-release tags) compare as strings.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split(['.', '-', '+']);
+    let mut b_parts = b.split(['.', '-', '+']);
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(x), Some(y)) => {
+                let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    _ => x.cmp(y),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+/// Span for one user-facing operation, tagged with a fresh correlation ID so
+/// every event logged while handling it can be grouped.
+pub(crate) fn operation_span(operation: &'static str, plugin_id: &str) -> tracing::Span {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let correlation_id = format!(
+        "{:x}-{:x}",
+        std::process::id(),
+        NEXT.fetch_add(1, AtomicOrdering::Relaxed)
+    );
+    tracing::info_span!("plugin_op", operation, plugin_id, correlation_id)
+}
+
+/// Cross-process advisory lock on one plugin's install directory.
+///
+/// An OS file lock (`flock`/`LockFileEx`) on `<id>.install.lock`, so it is held
+/// for as long as the operation takes and released by the OS when the handle is
+/// dropped or the holding process dies. The file is never deleted: removing it
+/// while another process waits on it would let a third lock a new file.
+pub(crate) struct InstallLock {
+    _file: std::fs::File,
+}
+
+impl InstallLock {
+    /// Lock `<id>.install.lock`, waiting up to `timeout` for a current holder.
+    pub(crate) async fn acquire(
+        install_dir: &Path,
+        id: &str,
+        timeout: Duration,
+    ) -> Result<Self, HostError> {
+        std::fs::create_dir_all(install_dir)?;
+        let path = install_dir.join(format!("{}{}", id, INSTALL_LOCK_SUFFIX));
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match file.try_lock() {
+                Ok(()) => {
+                    // The PID is informational only, for someone inspecting a stuck install
+                    use std::io::Write;
+                    let _ = file.set_len(0);
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(Self { _file: file });
+                }
+                Err(std::fs::TryLockError::WouldBlock) => {
+                    if Instant::now() >= deadline {
+                        return Err(HostError::InstallInProgress(id.to_string()));
+                    }
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                Err(std::fs::TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Adapt a byte-progress callback to phase reporting, forwarding only download progress.
+fn downloads_only(
+    on_progress: impl Fn(u64, u64) + Send + Sync,
+) -> impl Fn(InstallPhase, u64, u64) + Send + Sync {
+    move |phase, done, total| {
+        if phase == InstallPhase::Downloading {
+            on_progress(done, total)
+        }
+    }
+}
+
+/// Wrap a downloaded archive in the decoder matching its compression magic bytes.
+fn decompress(bytes: &[u8]) -> Result<Box<dyn std::io::Read + '_>, HostError> {
+    const GZIP: &[u8] = &[0x1f, 0x8b];
+    const ZSTD: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+    const XZ: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+    if bytes.starts_with(GZIP) {
+        Ok(Box::new(flate2::read::GzDecoder::new(bytes)))
+    } else if bytes.starts_with(ZSTD) {
+        Ok(Box::new(zstd::Decoder::new(bytes)?))
+    } else if bytes.starts_with(XZ) {
+        Ok(Box::new(xz2::read::XzDecoder::new(bytes)))
+    } else {
+        let magic: Vec<String> = bytes.iter().take(6).map(|b| format!("{:02x}", b)).collect();
+        Err(HostError::UnsupportedArchiveFormat(format!(
+            "unrecognized magic bytes {}",
+            magic.join(" ")
+        )))
+    }
+}
+
+/// List all regular files under `dir`, relative to it, sorted.
+fn list_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                walk(root, &path, files)?;
+            } else if file_type.is_file() {
+                files.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Fail with [`HostError::ChecksumMismatch`] unless `actual` equals `expected`.
+fn check_checksum(id: &str, actual: &str, expected: &str) -> Result<(), HostError> {
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(HostError::ChecksumMismatch {
+            id: id.to_string(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        })
+    }
+}
+
+/// Hex-encoded SHA-256 of a byte slice.
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Hex-encoded SHA-256 of a file's contents.
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    Ok(sha256_hex(&std::fs::read(path)?))
+}
+
+/// Write `sha256sum`-style checksums of every file under `version_dir` to `sidecar`.
+fn write_checksums(version_dir: &Path, sidecar: &Path) -> std::io::Result<()> {
+    let mut out = String::new();
+    for file in list_files(version_dir)? {
+        let checksum = sha256_file(&version_dir.join(&file))?;
+        let file = file.to_string_lossy().replace('\\', "/");
+        out.push_str(&format!("{}  {}\n", checksum, file));
+    }
+    std::fs::write(sidecar, out)
+}
+
+/// Set executable permissions on non-text files in a directory (Unix only).
+#[cfg(unix)]
+async fn set_unix_permissions(dir: &PathBuf) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.is_file() {
+            if let Ok(metadata) = tokio::fs::metadata(&path).await {
+                let mut perms = metadata.permissions();
+                if !path
+                    .extension()
+                    .is_some_and(|e| e == "json" || e == "toml" || e == "txt" || e == "md")
+                {
+                    perms.set_mode(0o755);
+                    let _ = tokio::fs::set_permissions(&path, perms).await;
+                }
+            }
+        }
+    }
+}
+
+/// Check if a string contains glob wildcards.
+pub fn is_glob_pattern(s: &str) -> bool {
+    s.contains('*')
+}
+
+/// Match a string against a simple glob pattern (supports `*` wildcard).
+pub fn matches_glob(s: &str, pattern: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    if parts.len() == 1 {
+        return s == pattern;
+    }
+
+    let mut pos = 0;
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            if !s.starts_with(part) {
+                return false;
+            }
+            pos = part.len();
+        } else if i == parts.len() - 1 {
+            if !s.ends_with(part) {
+                return false;
+            }
+        } else if let Some(found_pos) = s[pos..].find(part) {
+            pos += found_pos + part.len();
+        } else {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
     fn test_is_glob_pattern() {
@@ -441,4 +1757,850 @@ mod tests {
         assert!(matches_glob("adi.lang.rust.plugin", "adi.*.plugin"));
         assert!(!matches_glob("adi.lang.rust.core", "adi.*.plugin"));
     }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.10.0", "0.9.1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2.0", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.2.1"), Ordering::Less);
+    }
+
+    fn deps_from(graph: &[(&str, &[&str])]) -> impl Fn(&str) -> Vec<String> {
+        let graph: HashMap<String, Vec<String>> = graph
+            .iter()
+            .map(|(id, deps)| (id.to_string(), deps.iter().map(|d| d.to_string()).collect()))
+            .collect();
+        move |id| graph.get(id).cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_resolve_load_order() {
+        let ids = vec![
+            "adi.app".to_string(),
+            "adi.core".to_string(),
+            "adi.lang".to_string(),
+        ];
+        let deps = deps_from(&[
+            ("adi.app", &["adi.lang", "adi.core"]),
+            ("adi.lang", &["adi.core"]),
+        ]);
+
+        assert_eq!(
+            resolve_load_order(&ids, deps).unwrap(),
+            vec!["adi.core", "adi.lang", "adi.app"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_load_order_cycle() {
+        let ids = vec!["adi.a".to_string(), "adi.b".to_string()];
+        let deps = deps_from(&[("adi.a", &["adi.b"]), ("adi.b", &["adi.a"])]);
+
+        match resolve_load_order(&ids, deps) {
+            Err(HostError::DependencyCycle(cycle)) => {
+                assert_eq!(cycle, vec!["adi.a", "adi.b", "adi.a"]);
+            }
+            other => panic!("expected cycle, got {:?}", other),
+        }
+    }
+
+    fn installer(tmp: &tempfile::TempDir) -> PluginInstaller {
+        PluginInstaller::new(
+            "http://localhost",
+            tmp.path().join("plugins"),
+            tmp.path().join("cache"),
+        )
+    }
+
+    fn write_version(installer: &PluginInstaller, id: &str, pointer: &str, present: &[&str]) {
+        let plugin_dir = installer.plugin_path(id);
+        for version in present {
+            let dir = plugin_dir.join(version);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("plugin.toml"), "").unwrap();
+        }
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(plugin_dir.join(".version"), pointer).unwrap();
+    }
+
+    fn write_manifest(installer: &PluginInstaller, id: &str, version: &str, deps: &[&str]) {
+        let version_dir = installer.plugin_path(id).join(version);
+        std::fs::create_dir_all(&version_dir).unwrap();
+        let deps = deps
+            .iter()
+            .map(|d| format!("\"{}\"", d))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let toml = format!(
+            r#"[plugin]
+id = "{id}"
+name = "Test"
+version = "{version}"
+type = "core"
+
+[binary]
+name = "plugin"
+
+[compatibility]
+depends_on = [{deps}]
+"#
+        );
+        std::fs::write(version_dir.join("plugin.toml"), toml).unwrap();
+        std::fs::write(installer.plugin_path(id).join(".version"), version).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_installed_load_order_reresolves_after_graph_change() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_manifest(&installer, "adi.a", "1.0.0", &["adi.b"]);
+        write_manifest(&installer, "adi.b", "1.0.0", &[]);
+
+        assert_eq!(
+            installer.installed_load_order().await.unwrap(),
+            vec!["adi.b", "adi.a"]
+        );
+        assert_eq!(
+            installer.installed_load_order().await.unwrap(),
+            vec!["adi.b", "adi.a"]
+        );
+
+        // Upgrade flips the dependency direction
+        write_manifest(&installer, "adi.a", "2.0.0", &[]);
+        write_manifest(&installer, "adi.b", "2.0.0", &["adi.a"]);
+
+        assert_eq!(
+            installer.installed_load_order().await.unwrap(),
+            vec!["adi.a", "adi.b"]
+        );
+
+        // Hand-edited manifests at the same versions flip it back
+        write_manifest(&installer, "adi.a", "2.0.0", &["adi.b"]);
+        write_manifest(&installer, "adi.b", "2.0.0", &[]);
+
+        assert_eq!(
+            installer.installed_load_order().await.unwrap(),
+            vec!["adi.b", "adi.a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_plugin_ids() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_manifest(&installer, "adi.hive", "1.0.0", &[]);
+        write_manifest(&installer, "adi.tasks", "1.0.0", &[]);
+
+        // A copied directory still declaring adi.hive
+        let copy = installer.plugin_path("adi.hive-copy").join("1.0.0");
+        std::fs::create_dir_all(&copy).unwrap();
+        std::fs::copy(
+            installer
+                .plugin_path("adi.hive")
+                .join("1.0.0")
+                .join("plugin.toml"),
+            copy.join("plugin.toml"),
+        )
+        .unwrap();
+        std::fs::write(
+            installer.plugin_path("adi.hive-copy").join(".version"),
+            "1.0.0",
+        )
+        .unwrap();
+
+        let duplicates = installer.duplicate_plugin_ids().await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].id, "adi.hive");
+        assert_eq!(
+            duplicates[0].paths,
+            vec![installer.plugin_path("adi.hive").join("1.0.0"), copy]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_installed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_manifest(&installer, "adi.hive", "1.0.0", &[]);
+        write_manifest(&installer, "adi.lang.rust", "0.3.0", &["adi.hive"]);
+
+        let manifest = installer
+            .plugin_path("adi.lang.rust")
+            .join("0.3.0")
+            .join("plugin.toml");
+        let toml = std::fs::read_to_string(&manifest).unwrap();
+        std::fs::write(
+            &manifest,
+            toml.replace(r#"type = "core""#, r#"type = "language-analyzer""#),
+        )
+        .unwrap();
+
+        let analyzers = installer
+            .installed_by_type("language-analyzer")
+            .await
+            .unwrap();
+        assert_eq!(analyzers.len(), 1);
+        assert_eq!(analyzers[0].id(), "adi.lang.rust");
+        assert_eq!(analyzers[0].package_id, "adi.lang.rust");
+
+        let dependents = installer
+            .find_installed(|p| {
+                p.manifest
+                    .compatibility
+                    .depends_on
+                    .contains(&"adi.hive".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert!(installer
+            .installed_providing("adi.search")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_package_size() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        let version_dir = installer.plugin_path("adi.hive").join("1.0.0");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(version_dir.join("plugin.toml"), [0u8; 100]).unwrap();
+        std::fs::write(installer.plugin_path("adi.hive").join(".version"), "1.0.0").unwrap();
+
+        assert_eq!(installer.package_size("adi.hive").unwrap(), 105);
+
+        let assets = version_dir.join("assets");
+        std::fs::create_dir_all(&assets).unwrap();
+        std::fs::write(assets.join("icon.png"), [0u8; 50]).unwrap();
+        assert_eq!(installer.package_size("adi.hive").unwrap(), 155);
+        assert_eq!(installer.total_installed_size().unwrap(), 155);
+
+        // Changes inside an existing nested directory
+        std::fs::write(assets.join("logo.png"), [0u8; 20]).unwrap();
+        assert_eq!(installer.package_size("adi.hive").unwrap(), 175);
+        std::fs::write(assets.join("icon.png"), [0u8; 10]).unwrap();
+        assert_eq!(installer.package_size("adi.hive").unwrap(), 135);
+
+        assert!(matches!(
+            installer.package_size("adi.tasks"),
+            Err(HostError::NotInstalled(_))
+        ));
+    }
+
+    #[test]
+    fn test_quarantine() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "1.0.0", &["1.0.0"]);
+
+        installer.record_load_failure("adi.hive").unwrap();
+        installer.record_load_success("adi.hive");
+        installer.record_load_failure("adi.hive").unwrap();
+        installer.record_load_failure("adi.hive").unwrap();
+        assert!(!installer.is_quarantined("adi.hive"));
+
+        assert_eq!(installer.record_load_failure("adi.hive").unwrap(), 3);
+        assert!(installer.is_quarantined("adi.hive"));
+        assert_eq!(installer.quarantined(), vec!["adi.hive".to_string()]);
+
+        installer.clear_quarantine("adi.hive");
+        assert!(installer.quarantined().is_empty());
+        assert!(installer.record_load_failure("adi.tasks").is_err());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[tokio::test]
+    async fn test_install_blocking_inside_runtime() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+
+        assert!(matches!(
+            installer.install_blocking("adi.hive", None),
+            Err(HostError::BlockingInRuntime(_))
+        ));
+    }
+
+    fn tar_with(file: &str, content: &[u8]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, file, content).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_decompress_detects_format() {
+        use std::io::{Read, Write};
+
+        let tar = tar_with("plugin.toml", b"[plugin]");
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar).unwrap();
+        let zst = zstd::encode_all(&tar[..], 0).unwrap();
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(&tar).unwrap();
+
+        for archive in [gz.finish().unwrap(), zst, xz.finish().unwrap()] {
+            let mut out = Vec::new();
+            decompress(&archive).unwrap().read_to_end(&mut out).unwrap();
+            assert_eq!(out, tar);
+        }
+
+        assert!(matches!(
+            decompress(&tar),
+            Err(HostError::UnsupportedArchiveFormat(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_lockfile_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "0.8.8", &["0.8.8"]);
+        let lock_path = tmp.path().join("plugins.lock");
+
+        // No install record yet
+        assert!(installer.write_lockfile(&lock_path).await.is_err());
+
+        installer
+            .write_install_record(&InstallRecord {
+                id: "adi.hive".to_string(),
+                version: "0.8.8".to_string(),
+                installed_at: 1_700_000_000,
+                source: "http://localhost".to_string(),
+                verified: false,
+                checksum: sha256_hex(b"archive"),
+            })
+            .unwrap();
+
+        let lockfile = installer.write_lockfile(&lock_path).await.unwrap();
+        assert_eq!(
+            lockfile.plugins,
+            vec![LockedPlugin {
+                id: "adi.hive".to_string(),
+                version: "0.8.8".to_string(),
+                checksum: sha256_hex(b"archive"),
+            }]
+        );
+
+        // Everything already matches the lock, so nothing is downloaded
+        let installed = installer.install_from_lockfile(&lock_path).await.unwrap();
+        assert!(installed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_install_lock_is_exclusive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let timeout = Duration::from_millis(200);
+
+        let lock = InstallLock::acquire(tmp.path(), "adi.hive", timeout)
+            .await
+            .unwrap();
+        assert!(matches!(
+            InstallLock::acquire(tmp.path(), "adi.hive", timeout).await,
+            Err(HostError::InstallInProgress(_))
+        ));
+        assert!(InstallLock::acquire(tmp.path(), "adi.tasks", timeout)
+            .await
+            .is_ok());
+
+        drop(lock);
+        assert!(InstallLock::acquire(tmp.path(), "adi.hive", timeout)
+            .await
+            .is_ok());
+
+        // A lock file left behind by a process that died holds no lock
+        std::fs::write(tmp.path().join("adi.crashed.install.lock"), "12345").unwrap();
+        assert!(
+            InstallLock::acquire(tmp.path(), "adi.crashed", Duration::ZERO)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_installed_unusual_names() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "vendor.café-ü", "1.0.0", &["1.0.0"]);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let name = std::ffi::OsStr::from_bytes(b"vendor.\xff");
+            let dir = tmp.path().join("plugins").join(name);
+            std::fs::create_dir_all(dir.join("1.0.0")).unwrap();
+            std::fs::write(dir.join("1.0.0").join("plugin.toml"), "").unwrap();
+            std::fs::write(dir.join(".version"), "1.0.0").unwrap();
+        }
+
+        assert_eq!(
+            installer.list_installed().await.unwrap(),
+            vec![("vendor.café-ü".to_string(), "1.0.0".to_string())]
+        );
+        assert!(installer.version_path("vendor.café-ü").unwrap().is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_system_plugins_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_system_plugins_dir(tmp.path().join("system"));
+        let installer = PluginInstaller::from_config(&config);
+        let system = PluginInstaller::new(
+            "http://localhost",
+            tmp.path().join("system"),
+            tmp.path().join("cache"),
+        );
+        write_manifest(&system, "adi.hive", "1.0.0", &[]);
+        write_manifest(&system, "adi.tasks", "1.0.0", &[]);
+        write_manifest(&installer, "adi.hive", "2.0.0", &[]);
+
+        assert_eq!(
+            installer.list_installed().await.unwrap(),
+            vec![
+                ("adi.hive".to_string(), "2.0.0".to_string()),
+                ("adi.tasks".to_string(), "1.0.0".to_string()),
+            ]
+        );
+        assert_eq!(
+            installer.plugin_source("adi.hive"),
+            Some(PluginSource::User)
+        );
+        assert_eq!(
+            installer.plugin_source("adi.tasks"),
+            Some(PluginSource::System)
+        );
+        assert_eq!(
+            installer.version_path("adi.tasks"),
+            Some(system.plugin_path("adi.tasks").join("1.0.0"))
+        );
+        assert!(matches!(
+            installer.uninstall("adi.tasks").await,
+            Err(HostError::ReadOnlyPlugin(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_system_plugin_helpers_use_system_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_system_plugins_dir(tmp.path().join("system"))
+            .with_quarantine_threshold(2);
+        let installer = PluginInstaller::from_config(&config);
+        let system = PluginInstaller::new(
+            "http://localhost",
+            tmp.path().join("system"),
+            tmp.path().join("cache"),
+        );
+        write_manifest(&system, "adi.tasks", "1.0.0", &[]);
+        let version_dir = system.plugin_path("adi.tasks").join("1.0.0");
+        std::fs::write(version_dir.join("plugin.bin"), b"binary").unwrap();
+
+        let plugins = installer.installed_plugins().await.unwrap();
+        assert!(plugins[0].path.starts_with(&version_dir));
+        assert!(installer.package_size("adi.tasks").unwrap() > 0);
+        assert!(installer
+            .package_files("adi.tasks")
+            .unwrap()
+            .contains(&PathBuf::from("1.0.0/plugin.bin")));
+        assert!(!installer
+            .validate("adi.tasks")
+            .iter()
+            .any(|issue| issue.message.contains("not installed")));
+
+        assert_eq!(installer.record_load_failure("adi.tasks").unwrap(), 1);
+        assert_eq!(installer.record_load_failure("adi.tasks").unwrap(), 2);
+        assert!(installer.is_quarantined("adi.tasks"));
+        assert!(!version_dir
+            .parent()
+            .unwrap()
+            .join(LOAD_FAILURES_FILE_NAME)
+            .exists());
+        assert_eq!(
+            installer.plugin_source("adi.tasks"),
+            Some(PluginSource::System)
+        );
+    }
+
+    #[test]
+    fn test_validate_finds_system_dependency() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_system_plugins_dir(tmp.path().join("system"));
+        let installer = PluginInstaller::from_config(&config);
+        let system = PluginInstaller::new(
+            "http://localhost",
+            tmp.path().join("system"),
+            tmp.path().join("cache"),
+        );
+        write_manifest(&system, "adi.hive", "1.0.0", &[]);
+        write_manifest(&installer, "adi.tasks", "1.0.0", &["adi.hive"]);
+
+        assert!(!installer
+            .validate("adi.tasks")
+            .iter()
+            .any(|issue| issue.message.contains("Dependency")));
+        assert!(
+            crate::validate_installed_plugin(installer.install_dir(), "adi.tasks")
+                .iter()
+                .any(|issue| issue.message.contains("Dependency adi.hive"))
+        );
+    }
+
+    #[test]
+    fn test_package_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "1.1.0", &["1.0.0", "1.1.0"]);
+        let assets = installer
+            .plugin_path("adi.hive")
+            .join("1.1.0")
+            .join("assets");
+        std::fs::create_dir_all(&assets).unwrap();
+        std::fs::write(assets.join("icon.svg"), "<svg/>").unwrap();
+
+        assert_eq!(
+            installer.package_files("adi.hive").unwrap(),
+            vec![
+                PathBuf::from(".version"),
+                PathBuf::from("1.0.0/plugin.toml"),
+                PathBuf::from("1.1.0/assets/icon.svg"),
+                PathBuf::from("1.1.0/plugin.toml"),
+            ]
+        );
+        assert!(matches!(
+            installer.package_files("adi.missing"),
+            Err(HostError::NotInstalled(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_and_blocklist() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_allowlist(["adi.*", "vendor.*"])
+            .with_blocked("vendor.bad");
+        let installer = PluginInstaller::from_config(&config);
+        for id in ["adi.hive", "vendor.good", "vendor.bad", "other.tool"] {
+            write_version(&installer, id, "1.0.0", &["1.0.0"]);
+        }
+
+        assert!(installer.is_permitted("adi.hive"));
+        assert!(!installer.is_permitted("vendor.bad"));
+        assert!(!installer.is_permitted("other.tool"));
+        assert_eq!(
+            installer.list_installed().await.unwrap(),
+            vec![
+                ("adi.hive".to_string(), "1.0.0".to_string()),
+                ("vendor.good".to_string(), "1.0.0".to_string()),
+            ]
+        );
+        assert!(matches!(
+            installer.install("vendor.bad", None, |_, _| {}).await,
+            Err(HostError::PluginNotPermitted(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_installed_plugins_skips_oversized_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_max_manifest_size(1024);
+        let installer = PluginInstaller::from_config(&config);
+        write_manifest(&installer, "adi.hive", "1.0.0", &[]);
+        write_manifest(&installer, "adi.tasks", "1.0.0", &[]);
+        let path = installer
+            .plugin_path("adi.tasks")
+            .join("1.0.0")
+            .join("plugin.toml");
+        let mut toml = std::fs::read_to_string(&path).unwrap();
+        toml.push_str(&format!("# {}\n", "x".repeat(4096)));
+        std::fs::write(&path, toml).unwrap();
+
+        let ids: Vec<String> = installer
+            .installed_plugins()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| p.package_id)
+            .collect();
+        assert_eq!(ids, vec!["adi.hive".to_string()]);
+        assert!(matches!(
+            installer.installed_manifest("adi.tasks"),
+            Err(HostError::ManifestTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_raw_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_manifest(&installer, "adi.hive", "1.0.0", &[]);
+        let path = installer
+            .plugin_path("adi.hive")
+            .join("1.0.0")
+            .join("plugin.toml");
+        let mut toml = std::fs::read_to_string(&path).unwrap();
+        toml.push_str("\n[x-experimental]\nlayout = \"grid\"\n");
+        std::fs::write(&path, &toml).unwrap();
+
+        assert_eq!(installer.raw_manifest("adi.hive").unwrap(), toml);
+        assert!(installer.installed_manifest("adi.hive").is_ok());
+        assert!(matches!(
+            installer.raw_manifest("adi.missing"),
+            Err(HostError::NotInstalled(_))
+        ));
+
+        let limited = PluginInstaller::from_config(
+            &crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+                .with_max_manifest_size(16),
+        );
+        assert!(matches!(
+            limited.raw_manifest("adi.hive"),
+            Err(HostError::ManifestTooLarge { limit: 16, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_uninstall_blocked_by_dependents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_manifest(&installer, "adi.hive", "1.0.0", &["adi.tasks"]);
+        write_manifest(&installer, "adi.tasks", "1.0.0", &[]);
+
+        match installer.uninstall("adi.tasks").await {
+            Err(HostError::DependencyInUse {
+                package,
+                required_by,
+            }) => {
+                assert_eq!(package, "adi.tasks");
+                assert_eq!(required_by, vec!["adi.hive".to_string()]);
+            }
+            other => panic!("expected DependencyInUse, got {:?}", other),
+        }
+        assert!(installer.is_installed("adi.tasks").is_some());
+
+        installer.uninstall_forced("adi.tasks").await.unwrap();
+        assert!(installer.is_installed("adi.tasks").is_none());
+    }
+
+    #[test]
+    fn test_verify_installed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "0.8.8", &["0.8.8"]);
+        let plugin_dir = installer.plugin_path("adi.hive");
+        let version_dir = plugin_dir.join("0.8.8");
+        std::fs::create_dir_all(version_dir.join("assets")).unwrap();
+        std::fs::write(version_dir.join("assets").join("icon.svg"), "<svg/>").unwrap();
+        write_checksums(&version_dir, &plugin_dir.join(CHECKSUMS_FILE_NAME)).unwrap();
+
+        installer.verify_installed("adi.hive").unwrap();
+
+        std::fs::write(version_dir.join("assets").join("icon.svg"), "<svg></svg>").unwrap();
+        std::fs::remove_file(version_dir.join("plugin.toml")).unwrap();
+
+        match installer.verify_installed("adi.hive") {
+            Err(HostError::IntegrityMismatch { files, .. }) => {
+                assert_eq!(files, vec!["assets/icon.svg", "plugin.toml"]);
+            }
+            other => panic!("expected integrity mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_install_record_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "0.8.8", &["0.8.8"]);
+        assert!(installer.install_record("adi.hive").is_none());
+
+        let record = InstallRecord {
+            id: "adi.hive".to_string(),
+            version: "0.8.8".to_string(),
+            installed_at: 1_700_000_000,
+            source: "http://localhost".to_string(),
+            verified: false,
+            checksum: sha256_hex(b"archive"),
+        };
+        installer.write_install_record(&record).unwrap();
+
+        assert_eq!(installer.install_record("adi.hive"), Some(record));
+    }
+
+    #[test]
+    fn test_check_version_pointer_dangling() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "0.9.0", &["0.8.8"]);
+
+        assert!(matches!(
+            installer.check_version_pointer("adi.hive"),
+            Err(HostError::DanglingVersion { version, .. }) if version == "0.9.0"
+        ));
+    }
+
+    struct RecordingRegistry {
+        requested: std::sync::Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RegistryBackend for RecordingRegistry {
+        async fn search(&self, query: &str) -> Result<SearchResults, HostError> {
+            Err(HostError::PackageNotFound(query.to_string()))
+        }
+
+        async fn list_plugins(&self) -> Result<Vec<PluginEntry>, HostError> {
+            Err(HostError::PackageNotFound(String::new()))
+        }
+
+        async fn get_plugin_latest(&self, id: &str) -> Result<PluginInfo, HostError> {
+            Err(HostError::PackageNotFound(id.to_string()))
+        }
+
+        async fn get_plugin_version(
+            &self,
+            id: &str,
+            version: &str,
+        ) -> Result<PluginInfo, HostError> {
+            self.requested.lock().unwrap().push(version.to_string());
+            Err(HostError::PackageNotFound(id.to_string()))
+        }
+
+        async fn download_plugin(
+            &self,
+            id: &str,
+            _: &str,
+            _: &str,
+            _: crate::ProgressFn<'_>,
+        ) -> Result<crate::Download, HostError> {
+            Err(HostError::PackageNotFound(id.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_state_restores_older_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let requested = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let installer = PluginInstaller::with_registry_backend(
+            &crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache")),
+            Box::new(RecordingRegistry {
+                requested: requested.clone(),
+            }),
+        );
+        write_manifest(&installer, "adi.hive", "2.0.0", &[]);
+
+        assert!(matches!(
+            installer
+                .install("adi.hive", Some("1.0.0"), |_, _| {})
+                .await,
+            Err(HostError::DowngradeBlocked { .. })
+        ));
+        assert!(requested.lock().unwrap().is_empty());
+
+        let snapshot = HostStateSnapshot {
+            plugins: vec![PluginSnapshot {
+                id: "adi.hive".to_string(),
+                version: "1.0.0".to_string(),
+                config: None,
+            }],
+        };
+        let report = installer.import_state(&snapshot).await;
+        assert_eq!(*requested.lock().unwrap(), vec!["1.0.0".to_string()]);
+        assert!(matches!(
+            report.failed.as_slice(),
+            [(_, HostError::PackageNotFound(_))]
+        ));
+    }
+
+    #[test]
+    fn test_try_from_config_rejects_invalid_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"));
+        assert!(PluginInstaller::try_from_config(&config).is_ok());
+
+        let config = config.with_registry("registry.example.com");
+        assert!(matches!(
+            PluginInstaller::try_from_config(&config),
+            Err(HostError::InvalidConfig(errors)) if errors.len() == 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_installed_plugins_dir_is_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("plugins");
+        std::fs::write(&file, "").unwrap();
+        let config = crate::PluginConfig::new(file, tmp.path().join("cache"));
+
+        assert!(matches!(
+            PluginInstaller::try_from_config(&config),
+            Err(HostError::InvalidConfig(_))
+        ));
+        let installer = PluginInstaller::from_config(&config);
+        match installer.list_installed().await {
+            Err(HostError::InvalidConfig(errors)) => {
+                assert!(errors[0].starts_with("plugins_dir is not a directory"))
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_checksum() {
+        let checksum = sha256_hex(b"archive");
+        assert!(check_checksum("adi.hive", &checksum, &checksum.to_uppercase()).is_ok());
+        assert!(matches!(
+            check_checksum("adi.hive", &checksum, &sha256_hex(b"tampered")),
+            Err(HostError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_downgrade() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "1.10.0", &["1.10.0"]);
+
+        assert!(installer.check_downgrade("adi.hive", "1.10.0").is_ok());
+        assert!(installer.check_downgrade("adi.hive", "1.11.0").is_ok());
+        assert!(installer.check_downgrade("adi.tasks", "0.1.0").is_ok());
+        assert!(matches!(
+            installer.check_downgrade("adi.hive", "1.9.0"),
+            Err(HostError::DowngradeBlocked { from, to, .. }) if from == "1.10.0" && to == "1.9.0"
+        ));
+    }
+
+    #[test]
+    fn test_repair_version_pointer_falls_back_to_highest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "1.0.0", &["0.8.8", "0.10.0"]);
+
+        assert_eq!(
+            installer.repair_version_pointer("adi.hive").unwrap(),
+            "0.10.0"
+        );
+        assert_eq!(installer.is_installed("adi.hive").unwrap(), "0.10.0");
+        assert_eq!(
+            installer.check_version_pointer("adi.hive").unwrap(),
+            "0.10.0"
+        );
+    }
+
+    #[test]
+    fn test_repair_version_pointer_nothing_to_fall_back_to() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = installer(&tmp);
+        write_version(&installer, "adi.hive", "1.0.0", &[]);
+
+        assert!(matches!(
+            installer.repair_version_pointer("adi.hive"),
+            Err(HostError::DanglingVersion { .. })
+        ));
+    }
 }
@@ -19,6 +19,7 @@
 //!         require_signatures: false,
 //!         trusted_keys: vec![],
 //!         host_version: "1.0.0".into(),
+//!         ..Default::default()
 //!     };
 //!
 //!     config.ensure_dirs()?;
@@ -43,6 +44,9 @@ mod config;
 mod error;
 mod installed;
 mod installer;
+mod manifest_cache;
+mod registry;
+mod validation;
 
 // V3 plugin support
 mod loader_v3;
@@ -52,6 +56,9 @@ pub use config::*;
 pub use error::*;
 pub use installed::*;
 pub use installer::*;
+pub use manifest_cache::*;
+pub use registry::*;
+pub use validation::*;
 
 // V3 exports
 pub use loader_v3::*;
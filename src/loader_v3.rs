@@ -13,9 +13,6 @@ pub struct LoadedPluginV3 {
     /// Plugin manifest
     pub manifest: PluginManifest,
 
-    /// Dynamic library handle
-    _library: Library,
-
     /// Plugin instance
     pub plugin: Arc<dyn Plugin>,
 
@@ -30,6 +27,12 @@ pub struct LoadedPluginV3 {
 
     /// Optional HTTP routes trait object (if plugin provides HTTP endpoints)
     pub http_routes: Option<Arc<dyn HttpRoutes>>,
+
+    /// Dynamic library handle.
+    ///
+    /// Declared last so it is dropped after every trait object above —
+    /// their vtables and code live inside this library.
+    pub(crate) library: Library,
 }
 
 impl LoadedPluginV3 {
@@ -39,12 +42,38 @@ impl LoadedPluginV3 {
     /// Wraps the load in `catch_unwind` and a timeout to guard against
     /// broken or ABI-incompatible plugins that crash or hang.
     pub async fn load(manifest: PluginManifest, plugin_dir: &Path) -> crate::Result<Self> {
-        let lib_path = resolve_plugin_binary(&manifest, plugin_dir)?;
+        Self::load_with_resolver(manifest, plugin_dir, &BinaryResolver::default()).await
+    }
+
+    /// Load a plugin, locating its binary with a custom [`BinaryResolver`].
+    ///
+    /// Use this with [`PluginConfig::binary_resolver`](crate::PluginConfig::binary_resolver)
+    /// for plugins whose binaries don't follow the default naming.
+    pub async fn load_with_resolver(
+        manifest: PluginManifest,
+        plugin_dir: &Path,
+        resolver: &BinaryResolver,
+    ) -> crate::Result<Self> {
+        let lib_path = resolver.resolve(&manifest, plugin_dir)?;
+        Self::load_from_binary(manifest, &lib_path).await
+    }
+
+    /// Load a plugin from an explicit binary path, bypassing binary resolution.
+    ///
+    /// Useful for debugging and for plugins built into nonstandard output
+    /// directories. The ABI check, init, and service discovery run as usual.
+    pub async fn load_from_binary(manifest: PluginManifest, lib_path: &Path) -> crate::Result<Self> {
+        if !lib_path.is_file() {
+            return Err(PluginError::PluginNotFound(format!(
+                "Plugin binary not found at {:?}",
+                lib_path
+            )));
+        }
         let plugin_id = manifest.plugin.id.clone();
 
         // Wrap the entire loading sequence in a timeout (10s) so a hung
         // dlopen / plugin_create / init cannot block the process forever.
-        let load_future = Self::load_inner(manifest, &lib_path, &plugin_id);
+        let load_future = Self::load_inner(manifest, lib_path, &plugin_id);
         match tokio::time::timeout(std::time::Duration::from_secs(10), load_future).await {
             Ok(result) => result,
             Err(_) => Err(PluginError::InitFailed(format!(
@@ -121,6 +150,17 @@ impl LoadedPluginV3 {
         let result: lib_plugin_abi_v3::Result<()> = plugin.init(&ctx).await;
         result.map_err(|e| PluginError::InitFailed(format!("Plugin init failed: {}", e)))?;
 
+        // The binary may have been rebuilt without updating its manifest
+        let reported = plugin.metadata();
+        if reported.version != manifest.plugin.version {
+            tracing::warn!(
+                plugin_id,
+                manifest_version = %manifest.plugin.version,
+                reported_version = %reported.version,
+                "Plugin reports a different version than its manifest"
+            );
+        }
+
         // Try to get CLI commands if the plugin provides them
         let cli_commands: Option<Arc<dyn CliCommands>> = if manifest.cli.is_some()
             || manifest.provides.iter().any(|s| s.id.ends_with(".cli"))
@@ -195,12 +235,12 @@ impl LoadedPluginV3 {
 
         Ok(Self {
             manifest,
-            _library: library,
             plugin: Arc::from(plugin),
             cli_commands,
             log_provider,
             daemon_service,
             http_routes,
+            library,
         })
     }
 
@@ -225,40 +265,91 @@ impl LoadedPluginV3 {
     }
 }
 
-/// Resolve plugin binary path
-fn resolve_plugin_binary(manifest: &PluginManifest, plugin_dir: &Path) -> crate::Result<PathBuf> {
-    let binary_name = &manifest.binary.name;
-
-    // Try platform-specific names
-    let candidates = if cfg!(target_os = "macos") {
-        vec![
-            format!("lib{}.dylib", binary_name),
-            format!("{}.dylib", binary_name),
-        ]
-    } else if cfg!(target_os = "linux") {
-        vec![
-            format!("lib{}.so", binary_name),
-            format!("{}.so", binary_name),
-        ]
-    } else if cfg!(target_os = "windows") {
-        vec![format!("{}.dll", binary_name)]
-    } else {
-        return Err(PluginError::PlatformNotSupported(
-            std::env::consts::OS.to_string()
-        ));
-    };
+/// Custom binary lookup: given a manifest and its plugin directory, return the binary path.
+pub type BinaryResolverFn = dyn Fn(&PluginManifest, &Path) -> Option<PathBuf> + Send + Sync;
+
+/// Locates a plugin's dynamic library inside its plugin directory.
+///
+/// The default strategy tries the platform's naming conventions for
+/// `manifest.binary.name`, lib-prefixed first (`libfoo.so`, then `foo.so`).
+/// A custom resolver, if set, is consulted first; returning `None` from it
+/// falls back to the default strategy.
+#[derive(Clone, Default)]
+pub struct BinaryResolver {
+    custom: Option<Arc<BinaryResolverFn>>,
+}
 
-    for candidate in candidates {
-        let path = plugin_dir.join(&candidate);
-        if path.exists() {
-            return Ok(path);
+impl BinaryResolver {
+    /// Create a resolver using only the default strategy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a resolver that tries `resolve` before the default strategy.
+    pub fn with_custom(
+        resolve: impl Fn(&PluginManifest, &Path) -> Option<PathBuf> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            custom: Some(Arc::new(resolve)),
         }
     }
 
-    Err(PluginError::PluginNotFound(format!(
-        "Plugin binary not found in {:?}",
-        plugin_dir
-    )))
+    /// File names the default strategy tries for `binary_name`, in order.
+    pub fn candidate_names(binary_name: &str) -> crate::Result<Vec<String>> {
+        let extension = if cfg!(target_os = "macos") {
+            "dylib"
+        } else if cfg!(target_os = "linux") {
+            "so"
+        } else if cfg!(target_os = "windows") {
+            "dll"
+        } else {
+            return Err(PluginError::PlatformNotSupported(
+                std::env::consts::OS.to_string()
+            ));
+        };
+
+        Ok(vec![
+            format!("lib{}.{}", binary_name, extension),
+            format!("{}.{}", binary_name, extension),
+        ])
+    }
+
+    /// Resolve the binary path for `manifest` inside `plugin_dir`.
+    pub fn resolve(&self, manifest: &PluginManifest, plugin_dir: &Path) -> crate::Result<PathBuf> {
+        if let Some(custom) = &self.custom {
+            if let Some(path) = custom(manifest, plugin_dir) {
+                return Ok(path);
+            }
+        }
+
+        for candidate in Self::candidate_names(&manifest.binary.name)? {
+            let path = plugin_dir.join(&candidate);
+            if path.exists() {
+                return Ok(path);
+            }
+        }
+
+        Err(PluginError::PluginNotFound(format!(
+            "Plugin binary not found in {:?}",
+            plugin_dir
+        )))
+    }
+}
+
+impl std::fmt::Debug for BinaryResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BinaryResolver")
+            .field("custom", &self.custom.is_some())
+            .finish()
+    }
+}
+
+/// Config directory of a plugin: ~/.config/adi/<plugin-id>/
+pub(crate) fn plugin_config_dir(plugin_id: &str) -> crate::Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| PluginError::InitFailed("Cannot determine config directory".to_string()))?
+        .join("adi")
+        .join(plugin_id))
 }
 
 /// Create plugin context
@@ -271,11 +362,7 @@ fn create_plugin_context(manifest: &PluginManifest) -> crate::Result<PluginConte
         .join("adi")
         .join(&plugin_id);
 
-    // Config directory: ~/.config/adi/<plugin-id>/
-    let config_dir = dirs::config_dir()
-        .ok_or_else(|| PluginError::InitFailed("Cannot determine config directory".to_string()))?
-        .join("adi")
-        .join(&plugin_id);
+    let config_dir = plugin_config_dir(&plugin_id)?;
 
     // Create directories if they don't exist
     std::fs::create_dir_all(&data_dir)?;
@@ -296,19 +383,122 @@ fn create_plugin_context(manifest: &PluginManifest) -> crate::Result<PluginConte
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn test_resolve_binary_name() {
-        // Test platform-specific binary name resolution
-        let name = if cfg!(target_os = "macos") {
-            "libplugin.dylib"
-        } else if cfg!(target_os = "linux") {
-            "libplugin.so"
+    use super::*;
+
+    fn manifest(binary_name: &str) -> PluginManifest {
+        let toml = format!(
+            r#"[plugin]
+id = "adi.test"
+name = "Test"
+version = "1.0.0"
+type = "core"
+
+[binary]
+name = "{binary_name}"
+"#
+        );
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("plugin.toml");
+        std::fs::write(&path, toml).unwrap();
+        PluginManifest::from_file(&path).unwrap()
+    }
+
+    fn extension() -> &'static str {
+        if cfg!(target_os = "macos") {
+            "dylib"
         } else if cfg!(target_os = "windows") {
-            "plugin.dll"
+            "dll"
         } else {
-            panic!("Unsupported platform");
-        };
+            "so"
+        }
+    }
+
+    #[test]
+    fn test_resolve_binary_name() {
+        let names = BinaryResolver::candidate_names("plugin").unwrap();
+        assert_eq!(
+            names,
+            vec![
+                format!("libplugin.{}", extension()),
+                format!("plugin.{}", extension()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_lib_prefixed_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(format!("libplugin.{}", extension()));
+        std::fs::write(&path, b"").unwrap();
 
-        assert!(!name.is_empty());
+        let resolved = BinaryResolver::new().resolve(&manifest("plugin"), tmp.path());
+        assert_eq!(resolved.unwrap(), path);
+    }
+
+    #[test]
+    fn test_resolve_bare_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(format!("plugin.{}", extension()));
+        std::fs::write(&path, b"").unwrap();
+
+        let resolved = BinaryResolver::new().resolve(&manifest("plugin"), tmp.path());
+        assert_eq!(resolved.unwrap(), path);
+    }
+
+    #[test]
+    fn test_resolve_missing_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let resolved = BinaryResolver::new().resolve(&manifest("plugin"), tmp.path());
+        assert!(matches!(resolved, Err(PluginError::PluginNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_load_from_missing_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("libplugin.so");
+
+        let result = LoadedPluginV3::load_from_binary(manifest("plugin"), &path).await;
+        assert!(matches!(result, Err(PluginError::PluginNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_load_from_invalid_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("libplugin.so");
+        std::fs::write(&path, b"not a library").unwrap();
+
+        let result = LoadedPluginV3::load_from_binary(manifest("plugin"), &path).await;
+        assert!(matches!(result, Err(PluginError::InitFailed(_))));
+    }
+
+    #[test]
+    fn test_custom_resolver() {
+        let tmp = tempfile::tempdir().unwrap();
+        let versioned = tmp.path().join(format!("plugin-1.0.0.{}", extension()));
+        std::fs::write(&versioned, b"").unwrap();
+
+        let resolver = BinaryResolver::with_custom(|manifest, dir| {
+            let path = dir.join(format!(
+                "{}-{}.{}",
+                manifest.binary.name,
+                manifest.plugin.version,
+                extension()
+            ));
+            path.exists().then_some(path)
+        });
+
+        let resolved = resolver.resolve(&manifest("plugin"), tmp.path());
+        assert_eq!(resolved.unwrap(), versioned);
+    }
+
+    #[test]
+    fn test_custom_resolver_falls_back_to_default() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(format!("libplugin.{}", extension()));
+        std::fs::write(&path, b"").unwrap();
+
+        let resolver = BinaryResolver::with_custom(|_, _| None);
+        let resolved = resolver.resolve(&manifest("plugin"), tmp.path());
+        assert_eq!(resolved.unwrap(), path);
     }
 }
@@ -1,10 +1,15 @@
 //! Plugin manager for v3 ABI
 
-use crate::LoadedPluginV3;
+use crate::{EnablePreflight, HostError, LoadedPluginV3, PluginInstaller, PreflightCheck};
 use lib_plugin_abi_v3::*;
+use libloading::Library;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use tracing::Instrument;
+
+/// How long [`PluginManagerV3::uninstall`] waits for a loaded plugin to shut down
+pub const UNINSTALL_SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 // Thread-local storage for current plugin manager
 thread_local! {
@@ -41,82 +46,128 @@ pub fn current_plugin_manager() -> Option<Arc<PluginManagerV3>> {
 /// Plugin manager for v3 plugins
 ///
 /// Manages loaded plugins and provides type-safe access to plugin services.
+///
+/// Listings (`list_plugins`, `all_*`) are ordered by key (plugin ID, language,
+/// provider, ...), so they are stable across runs.
+///
+/// # Drop order
+///
+/// When the manager is dropped it releases, in this order:
+///
+/// 1. every service trait object (CLI, HTTP, logs, daemon, ...),
+/// 2. the base plugin instances, in reverse registration order,
+/// 3. the dynamic libraries, in reverse registration order.
+///
+/// Libraries are never unloaded while the manager still holds code from
+/// them. Dropping does not call `Plugin::shutdown` (it is async); call
+/// [`PluginManagerV3::shutdown`] first for a graceful teardown. A manager
+/// installed with [`set_current_plugin_manager`] stays alive until
+/// [`clear_current_plugin_manager`] is called on that thread.
 pub struct PluginManagerV3 {
     /// All loaded plugins
-    plugins: HashMap<String, Arc<dyn Plugin>>,
+    plugins: BTreeMap<String, Arc<dyn Plugin>>,
+
+    /// Plugin IDs in registration order (dependencies are registered first)
+    load_order: Vec<String>,
+
+    /// Libraries backing registered plugins, kept alive until the manager is dropped
+    libraries: Vec<Library>,
 
     /// Service-specific lookups
-    cli_commands: HashMap<String, Arc<dyn cli::CliCommands>>,
-    http_routes: HashMap<String, Arc<dyn http::HttpRoutes>>,
+    cli_commands: BTreeMap<String, Arc<dyn cli::CliCommands>>,
+    http_routes: BTreeMap<String, Arc<dyn http::HttpRoutes>>,
     // Language analyzer traits (keyed by language name, e.g., "rust", "python")
-    language_analyzers: HashMap<String, Arc<dyn lang::LanguageAnalyzer>>,
+    language_analyzers: BTreeMap<String, Arc<dyn lang::LanguageAnalyzer>>,
+    // Returned when no analyzer is registered for a language
+    fallback_analyzer: Option<Arc<dyn lang::LanguageAnalyzer>>,
+    // File extension (lowercase, no dot) -> language name, overriding the built-in map
+    analyzer_extensions: BTreeMap<String, String>,
 
     // Embedder trait (keyed by provider name, e.g., "fastembed", "openai")
-    embedders: HashMap<String, Arc<dyn embed::Embedder>>,
+    embedders: BTreeMap<String, Arc<dyn embed::Embedder>>,
+
+    // Preferred embedder provider for `get_default_embedder`
+    default_embedder: Option<String>,
 
     // Orchestration traits
-    runners: HashMap<String, Arc<dyn runner::Runner>>,
-    health_checks: HashMap<String, Arc<dyn health::HealthCheck>>,
-    env_providers: HashMap<String, Arc<dyn env::EnvProvider>>,
-    proxy_middleware: HashMap<String, Arc<dyn proxy::ProxyMiddleware>>,
-    obs_sinks: HashMap<String, Arc<dyn obs::ObservabilitySink>>,
-    rollout_strategies: HashMap<String, Arc<dyn rollout::RolloutStrategy>>,
+    runners: BTreeMap<String, Arc<dyn runner::Runner>>,
+    health_checks: BTreeMap<String, Arc<dyn health::HealthCheck>>,
+    env_providers: BTreeMap<String, Arc<dyn env::EnvProvider>>,
+    proxy_middleware: BTreeMap<String, Arc<dyn proxy::ProxyMiddleware>>,
+    obs_sinks: BTreeMap<String, Arc<dyn obs::ObservabilitySink>>,
+    rollout_strategies: BTreeMap<String, Arc<dyn rollout::RolloutStrategy>>,
 
     // Log streaming
-    log_providers: HashMap<String, Arc<dyn logs::LogProvider>>,
+    log_providers: BTreeMap<String, Arc<dyn logs::LogProvider>>,
 
     // Daemon services
-    daemon_services: HashMap<String, Arc<dyn daemon::DaemonService>>,
+    daemon_services: BTreeMap<String, Arc<dyn daemon::DaemonService>>,
 }
 
 impl PluginManagerV3 {
     /// Create a new plugin manager
     pub fn new() -> Self {
         Self {
-            plugins: HashMap::new(),
-            cli_commands: HashMap::new(),
-            http_routes: HashMap::new(),
-            language_analyzers: HashMap::new(),
-            embedders: HashMap::new(),
-            runners: HashMap::new(),
-            health_checks: HashMap::new(),
-            env_providers: HashMap::new(),
-            proxy_middleware: HashMap::new(),
-            obs_sinks: HashMap::new(),
-            rollout_strategies: HashMap::new(),
-            log_providers: HashMap::new(),
-            daemon_services: HashMap::new(),
+            plugins: BTreeMap::new(),
+            load_order: Vec::new(),
+            libraries: Vec::new(),
+            cli_commands: BTreeMap::new(),
+            http_routes: BTreeMap::new(),
+            language_analyzers: BTreeMap::new(),
+            fallback_analyzer: None,
+            analyzer_extensions: BTreeMap::new(),
+            embedders: BTreeMap::new(),
+            default_embedder: None,
+            runners: BTreeMap::new(),
+            health_checks: BTreeMap::new(),
+            env_providers: BTreeMap::new(),
+            proxy_middleware: BTreeMap::new(),
+            obs_sinks: BTreeMap::new(),
+            rollout_strategies: BTreeMap::new(),
+            log_providers: BTreeMap::new(),
+            daemon_services: BTreeMap::new(),
         }
     }
 
     /// Register a loaded plugin
     pub fn register(&mut self, loaded: LoadedPluginV3) -> lib_plugin_abi_v3::Result<()> {
         let plugin_id = loaded.metadata().id.clone();
-        let plugin = loaded.plugin;
+        let LoadedPluginV3 {
+            plugin,
+            cli_commands,
+            log_provider,
+            daemon_service,
+            http_routes,
+            library,
+            ..
+        } = loaded;
+        self.libraries.push(library);
 
         // Store base plugin
         self.plugins.insert(plugin_id.clone(), plugin.clone());
+        self.load_order.retain(|id| id != &plugin_id);
+        self.load_order.push(plugin_id.clone());
 
         // Register CLI commands if available
-        if let Some(cli) = loaded.cli_commands {
+        if let Some(cli) = cli_commands {
             self.cli_commands.insert(plugin_id.clone(), cli);
             tracing::debug!("Registered CLI commands for plugin: {}", plugin_id);
         }
 
         // Register log provider if available
-        if let Some(log_provider) = loaded.log_provider {
+        if let Some(log_provider) = log_provider {
             self.log_providers.insert(plugin_id.clone(), log_provider);
             tracing::debug!("Registered log provider for plugin: {}", plugin_id);
         }
 
         // Register daemon service if available
-        if let Some(daemon_service) = loaded.daemon_service {
+        if let Some(daemon_service) = daemon_service {
             self.daemon_services.insert(plugin_id.clone(), daemon_service);
             tracing::debug!("Registered daemon service for plugin: {}", plugin_id);
         }
 
         // Register HTTP routes if available
-        if let Some(http_routes) = loaded.http_routes {
+        if let Some(http_routes) = http_routes {
             self.http_routes.insert(plugin_id.clone(), http_routes);
             tracing::debug!("Registered HTTP routes for plugin: {}", plugin_id);
         }
@@ -124,6 +175,239 @@ impl PluginManagerV3 {
         Ok(())
     }
 
+    /// Load and register every installed plugin, dependencies first.
+    ///
+    /// The load order is computed once for the whole installed set. A plugin
+    /// that fails to load does not abort the rest, but plugins depending on it
+    /// are skipped. If two install directories declare the same plugin ID, the
+    /// first in load order wins and the other fails. Returns each plugin's result in load order, or an error if
+    /// the dependency graph has a cycle.
+    ///
+    /// Consecutive failures to load or initialize a plugin's binary are recorded
+    /// per plugin; quarantined plugins are skipped until
+    /// [`PluginInstaller::clear_quarantine`] or a reinstall. Unmet preconditions,
+    /// such as a missing dependency or a duplicate plugin ID, are not counted.
+    pub async fn load_all_installed(
+        &mut self,
+        installer: &PluginInstaller,
+    ) -> crate::Result<Vec<(String, crate::Result<PluginMetadata>)>> {
+        let order = installer.installed_load_order().await?;
+        let mut results: Vec<(String, crate::Result<PluginMetadata>)> = Vec::with_capacity(order.len());
+
+        for plugin_id in order {
+            let failed_dep = installer.get_dependencies(&plugin_id).into_iter().find(|dep| {
+                results
+                    .iter()
+                    .any(|(id, result)| id == dep && result.is_err())
+            });
+
+            let result = match failed_dep {
+                Some(dep) => Err(HostError::LoadFailed(format!(
+                    "Dependency {} of {} failed to load",
+                    dep, plugin_id
+                ))),
+                None if installer.is_quarantined(&plugin_id) => Err(HostError::Quarantined(plugin_id.clone())),
+                None => match self.load_inputs(installer, &plugin_id) {
+                    Err(e) => Err(e),
+                    Ok((manifest, version_dir)) => {
+                        let span = crate::installer::operation_span("load", &plugin_id);
+                        let loaded = LoadedPluginV3::load_with_resolver(manifest, &version_dir, installer.binary_resolver())
+                            .instrument(span.clone())
+                            .await;
+                        match loaded {
+                            Ok(loaded) => {
+                                installer.record_load_success(&plugin_id);
+                                self.register_loaded(loaded, &span)
+                            }
+                            Err(e) => {
+                                if let Err(e) = installer.record_load_failure(&plugin_id) {
+                                    tracing::warn!(plugin_id, error = %e, "Failed to record load failure");
+                                }
+                                Err(e)
+                            }
+                        }
+                    }
+                },
+            };
+
+            if let Err(e) = &result {
+                tracing::warn!(plugin_id, error = %e, "Failed to load plugin");
+            }
+            results.push((plugin_id, result));
+        }
+
+        Ok(results)
+    }
+
+    /// Check whether an installed plugin could be enabled right now, without loading it
+    ///
+    /// Covers the manifest, the binary for this platform, quarantine, whether
+    /// the plugin ID is already taken, and whether every dependency is loaded.
+    pub fn can_enable(&self, installer: &PluginInstaller, plugin_id: &str) -> EnablePreflight {
+        fn check(name: &'static str, failure: Option<String>) -> PreflightCheck {
+            PreflightCheck { name, passed: failure.is_none(), detail: failure }
+        }
+
+        let mut checks = Vec::new();
+        let manifest = installer.installed_manifest(plugin_id);
+        checks.push(check("manifest", manifest.as_ref().err().map(|e| e.to_string())));
+
+        if let (Ok(manifest), Some(version_dir)) = (&manifest, installer.version_path(plugin_id)) {
+            checks.push(check(
+                "binary",
+                installer
+                    .binary_resolver()
+                    .resolve(manifest, &version_dir)
+                    .err()
+                    .map(|e| e.to_string()),
+            ));
+            checks.push(check(
+                "not_loaded",
+                self.plugins
+                    .contains_key(&manifest.plugin.id)
+                    .then(|| format!("{} is already loaded", manifest.plugin.id)),
+            ));
+        }
+
+        checks.push(check(
+            "quarantine",
+            installer
+                .is_quarantined(plugin_id)
+                .then(|| format!("{} failed to load too many times in a row", plugin_id)),
+        ));
+
+        let missing: Vec<String> = installer
+            .get_dependencies(plugin_id)
+            .into_iter()
+            .filter(|dep| !self.plugins.contains_key(dep))
+            .collect();
+        checks.push(check(
+            "dependencies",
+            (!missing.is_empty()).then(|| format!("Not loaded: {}", missing.join(", "))),
+        ));
+
+        EnablePreflight { plugin_id: plugin_id.to_string(), checks }
+    }
+
+    /// Load and register one installed plugin
+    ///
+    /// Its dependencies must already be loaded; use [`Self::load_all_installed`]
+    /// to load a whole installed set in dependency order. Returns the metadata
+    /// the binary reports, which may differ from the installed manifest.
+    pub async fn load_installed(&mut self, installer: &PluginInstaller, plugin_id: &str) -> crate::Result<PluginMetadata> {
+        let (manifest, version_dir) = self.load_inputs(installer, plugin_id)?;
+        let span = crate::installer::operation_span("load", plugin_id);
+        let loaded = LoadedPluginV3::load_with_resolver(manifest, &version_dir, installer.binary_resolver())
+            .instrument(span.clone())
+            .await?;
+        self.register_loaded(loaded, &span)
+    }
+
+    /// Check that an installed plugin may be loaded now and return its manifest and version directory
+    fn load_inputs(
+        &self,
+        installer: &PluginInstaller,
+        plugin_id: &str,
+    ) -> crate::Result<(lib_plugin_manifest::PluginManifest, std::path::PathBuf)> {
+        if !installer.is_permitted(plugin_id) {
+            return Err(HostError::PluginNotPermitted(plugin_id.to_string()));
+        }
+        if let Some(dep) = installer
+            .get_dependencies(plugin_id)
+            .into_iter()
+            .find(|dep| !self.plugins.contains_key(dep))
+        {
+            return Err(HostError::LoadFailed(format!(
+                "Dependency {} of {} is not loaded",
+                dep, plugin_id
+            )));
+        }
+
+        let manifest = installer.installed_manifest(plugin_id)?;
+        if self.plugins.contains_key(&manifest.plugin.id) {
+            return Err(HostError::LoadFailed(format!(
+                "{} declares plugin ID {}, which is already loaded",
+                plugin_id, manifest.plugin.id
+            )));
+        }
+        let version_dir = installer
+            .version_path(plugin_id)
+            .ok_or_else(|| HostError::NotInstalled(plugin_id.to_string()))?;
+        Ok((manifest, version_dir))
+    }
+
+    /// Register a freshly loaded plugin within its load span
+    fn register_loaded(&mut self, loaded: LoadedPluginV3, span: &tracing::Span) -> crate::Result<PluginMetadata> {
+        let metadata = loaded.metadata();
+        span.in_scope(|| self.register(loaded))?;
+        tracing::info!(plugin_id = %metadata.id, version = %metadata.version, "Loaded plugin");
+        Ok(metadata)
+    }
+
+    /// Load and initialize an installed plugin, then shut it down and unload it again
+    ///
+    /// A smoke test for packaging pipelines: nothing is registered and the plugin
+    /// stays unloaded. Returns the metadata the binary reported during the probe.
+    pub async fn probe_installed(&self, installer: &PluginInstaller, plugin_id: &str) -> crate::Result<PluginMetadata> {
+        let manifest = installer.installed_manifest(plugin_id)?;
+        if self.plugins.contains_key(&manifest.plugin.id) {
+            return Err(HostError::LoadFailed(format!(
+                "{} is loaded; probing would initialize it a second time",
+                manifest.plugin.id
+            )));
+        }
+        let version_dir = installer
+            .version_path(plugin_id)
+            .ok_or_else(|| HostError::NotInstalled(plugin_id.to_string()))?;
+
+        async {
+            let loaded = LoadedPluginV3::load_with_resolver(manifest, &version_dir, installer.binary_resolver()).await?;
+            let metadata = loaded.metadata();
+            loaded.unload().await?;
+            Ok(metadata)
+        }
+        .instrument(crate::installer::operation_span("probe", plugin_id))
+        .await
+    }
+
+    /// Uninstall a plugin, letting it flush state first if it is loaded
+    ///
+    /// The installer's checks (read-only system plugin, dependents, install lock)
+    /// run first, so a refused uninstall leaves the plugin loaded. Then a loaded
+    /// plugin gets `Plugin::shutdown`, bounded by [`UNINSTALL_SHUTDOWN_TIMEOUT`],
+    /// and is unregistered before its files are deleted, with the lock held
+    /// throughout. The hook cannot veto the uninstall: a failed or timed-out
+    /// shutdown is logged and removal goes ahead. Plugins that aren't loaded
+    /// skip the hook. The plugin's library stays mapped until the manager is dropped.
+    pub async fn uninstall(&mut self, installer: &PluginInstaller, plugin_id: &str) -> crate::Result<()> {
+        async {
+            let lock = installer.begin_uninstall(plugin_id, false).await?;
+            self.before_uninstall(plugin_id).await;
+            installer.finish_uninstall(plugin_id, lock).await
+        }
+        .instrument(crate::installer::operation_span("uninstall", plugin_id))
+        .await
+    }
+
+    /// Shut down and unregister a loaded plugin that is about to be uninstalled
+    async fn before_uninstall(&mut self, plugin_id: &str) {
+        if let Some(plugin) = self.plugins.get(plugin_id).cloned() {
+            // Shut down first so the plugin's services stay registered while it cleans up
+            match tokio::time::timeout(UNINSTALL_SHUTDOWN_TIMEOUT, plugin.shutdown()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::warn!(plugin_id, error = %e, "Plugin shutdown failed before uninstall"),
+                Err(_) => tracing::warn!(plugin_id, "Plugin shutdown timed out before uninstall"),
+            }
+
+            self.cli_commands.remove(plugin_id);
+            self.http_routes.remove(plugin_id);
+            self.log_providers.remove(plugin_id);
+            self.daemon_services.remove(plugin_id);
+            self.load_order.retain(|id| id != plugin_id);
+            self.plugins.remove(plugin_id);
+        }
+    }
+
     /// Register a CLI commands plugin
     pub fn register_cli_commands(&mut self, plugin_id: impl Into<String>, plugin: Arc<dyn cli::CliCommands>) {
         self.cli_commands.insert(plugin_id.into(), plugin);
@@ -201,11 +485,37 @@ impl PluginManagerV3 {
         self.env_providers.get(provider_type).cloned()
     }
 
+    /// Register a proxy middleware plugin
+    pub fn register_proxy_middleware(&mut self, middleware_type: impl Into<String>, plugin: Arc<dyn proxy::ProxyMiddleware>) {
+        self.proxy_middleware.insert(middleware_type.into(), plugin);
+    }
+
     /// Get a proxy middleware plugin
     pub fn get_proxy_middleware(&self, middleware_type: &str) -> Option<Arc<dyn proxy::ProxyMiddleware>> {
         self.proxy_middleware.get(middleware_type).cloned()
     }
 
+    /// Compose registered proxy middleware in the given order
+    ///
+    /// Fails with every name that is not registered, not just the first.
+    pub fn build_middleware_chain(&self, order: &[String]) -> crate::Result<ComposedMiddleware> {
+        let missing: Vec<String> = order
+            .iter()
+            .filter(|name| !self.proxy_middleware.contains_key(name.as_str()))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            return Err(HostError::MissingMiddleware(missing));
+        }
+
+        Ok(ComposedMiddleware {
+            chain: order
+                .iter()
+                .map(|name| (name.clone(), self.proxy_middleware[name.as_str()].clone()))
+                .collect(),
+        })
+    }
+
     /// Get an observability sink plugin
     pub fn get_obs_sink(&self, sink_type: &str) -> Option<Arc<dyn obs::ObservabilitySink>> {
         self.obs_sinks.get(sink_type).cloned()
@@ -249,9 +559,27 @@ impl PluginManagerV3 {
         self.language_analyzers.insert(language.into(), plugin);
     }
 
+    /// Register the analyzer used for languages without a dedicated one (e.g. plaintext)
+    pub fn register_fallback_analyzer(&mut self, plugin: Arc<dyn lang::LanguageAnalyzer>) {
+        self.fallback_analyzer = Some(plugin);
+    }
+
     /// Get a language analyzer plugin by language name (e.g., "rust", "python")
+    ///
+    /// Falls back to the analyzer from `register_fallback_analyzer`, if any.
+    /// Use `find_language_analyzer` to tell the two apart.
     pub fn get_language_analyzer(&self, language: &str) -> Option<Arc<dyn lang::LanguageAnalyzer>> {
-        self.language_analyzers.get(language).cloned()
+        self.find_language_analyzer(language).map(|found| found.analyzer)
+    }
+
+    /// Get a language analyzer, reporting whether it is the fallback
+    pub fn find_language_analyzer(&self, language: &str) -> Option<AnalyzerMatch> {
+        if let Some(analyzer) = self.language_analyzers.get(language) {
+            return Some(AnalyzerMatch { analyzer: analyzer.clone(), is_fallback: false });
+        }
+        self.fallback_analyzer
+            .clone()
+            .map(|analyzer| AnalyzerMatch { analyzer, is_fallback: true })
     }
 
     /// Get all language analyzer plugins
@@ -267,6 +595,46 @@ impl PluginManagerV3 {
         self.language_analyzers.contains_key(language)
     }
 
+    /// Map a file extension (without the dot) to a language name for `analyzer_for_path`
+    ///
+    /// Overrides the built-in mapping for that extension.
+    pub fn register_analyzer_extension(&mut self, extension: impl Into<String>, language: impl Into<String>) {
+        self.analyzer_extensions.insert(extension.into().to_ascii_lowercase(), language.into());
+    }
+
+    /// Get the language analyzer for a file, based on its extension
+    ///
+    /// Extensions registered with `register_analyzer_extension` win over the
+    /// built-in map; an unknown extension is tried as a language name itself
+    /// (e.g. `go`, `zig`).
+    pub fn analyzer_for_path(&self, path: &std::path::Path) -> Option<Arc<dyn lang::LanguageAnalyzer>> {
+        self.language_for_path(path)
+            .and_then(|language| self.get_language_analyzer(&language))
+    }
+
+    fn language_for_path(&self, path: &std::path::Path) -> Option<String> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        if let Some(language) = self.analyzer_extensions.get(&extension) {
+            return Some(language.clone());
+        }
+        let language = match extension.as_str() {
+            "rs" => "rust",
+            "py" | "pyi" => "python",
+            "ts" | "tsx" | "mts" | "cts" => "typescript",
+            "js" | "jsx" | "mjs" | "cjs" => "javascript",
+            "c" | "h" => "c",
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" => "cpp",
+            "cs" => "csharp",
+            "rb" => "ruby",
+            "kt" | "kts" => "kotlin",
+            "sh" | "bash" => "shell",
+            "md" | "markdown" => "markdown",
+            "yml" | "yaml" => "yaml",
+            other => other,
+        };
+        Some(language.to_string())
+    }
+
     /// Register an embedder plugin
     pub fn register_embedder(&mut self, provider: impl Into<String>, plugin: Arc<dyn embed::Embedder>) {
         self.embedders.insert(provider.into(), plugin);
@@ -277,9 +645,32 @@ impl PluginManagerV3 {
         self.embedders.get(provider).cloned()
     }
 
-    /// Get the default embedder (first available)
+    /// Set the provider returned by `get_default_embedder`
+    pub fn set_default_embedder(&mut self, provider: impl Into<String>) {
+        self.default_embedder = Some(provider.into());
+    }
+
+    /// Get the default embedder
+    ///
+    /// Returns the provider set with `set_default_embedder` if it is registered,
+    /// otherwise the first provider in name order.
     pub fn get_default_embedder(&self) -> Option<Arc<dyn embed::Embedder>> {
-        self.embedders.values().next().cloned()
+        self.default_embedder
+            .as_deref()
+            .and_then(|provider| self.embedders.get(provider))
+            .or_else(|| self.embedders.values().next())
+            .cloned()
+    }
+
+    /// Select an embedder by provider preference
+    ///
+    /// Returns the first provider in `req.provider_pref` that is registered,
+    /// falling back to `get_default_embedder` if none of them is.
+    pub fn select_embedder(&self, req: &EmbedderRequirements) -> Option<Arc<dyn embed::Embedder>> {
+        req.provider_pref
+            .iter()
+            .find_map(|provider| self.embedders.get(provider).cloned())
+            .or_else(|| self.get_default_embedder())
     }
 
     /// Get all embedder plugins
@@ -295,11 +686,32 @@ impl PluginManagerV3 {
         !self.embedders.is_empty()
     }
 
+    /// Check if a plugin is loaded
+    pub fn is_loaded(&self, plugin_id: &str) -> bool {
+        self.plugins.contains_key(plugin_id)
+    }
+
+    /// Loaded plugins whose install has since disappeared from disk, in ID order
+    ///
+    /// Rescanning the installer never touches loaded plugins, so a plugin removed
+    /// behind the manager's back stays loaded but unlisted. Use this to find
+    /// them, e.g. to warn or restart.
+    pub fn orphaned_plugins(&self, installer: &PluginInstaller) -> Vec<String> {
+        not_installed(self.plugins.keys(), installer)
+    }
+
     /// Get a plugin by ID
     pub fn get_plugin(&self, plugin_id: &str) -> Option<Arc<dyn Plugin>> {
         self.plugins.get(plugin_id).cloned()
     }
 
+    /// Get a loaded plugin's self-reported metadata
+    ///
+    /// This comes from the binary and may differ from the installed manifest.
+    pub fn plugin_metadata(&self, plugin_id: &str) -> Option<PluginMetadata> {
+        self.plugins.get(plugin_id).map(|p| p.metadata())
+    }
+
     /// List all loaded plugins
     pub fn list_plugins(&self) -> Vec<PluginMetadata> {
         self.plugins
@@ -308,18 +720,50 @@ impl PluginManagerV3 {
             .collect()
     }
 
-    /// Unload all plugins
-    pub async fn shutdown_all(&mut self) -> lib_plugin_abi_v3::Result<()> {
-        for (_id, plugin) in self.plugins.drain() {
+    /// Shut down all plugins and clear every service registry.
+    ///
+    /// Plugins are shut down in reverse registration order, so dependents go
+    /// before the plugins they depend on. A failing plugin does not stop the
+    /// rest; every error is collected and returned alongside its plugin ID.
+    pub async fn shutdown(&mut self) -> Vec<(String, PluginError)> {
+        let mut errors = Vec::new();
+
+        for plugin_id in std::mem::take(&mut self.load_order).into_iter().rev() {
+            let Some(plugin) = self.plugins.remove(&plugin_id) else {
+                continue;
+            };
             if let Err(e) = plugin.shutdown().await {
-                eprintln!("Error shutting down plugin: {}", e);
+                errors.push((plugin_id, e));
             }
         }
 
-        // Clear all service registries
+        // Anything registered without going through `register`
+        for (plugin_id, plugin) in std::mem::take(&mut self.plugins) {
+            if let Err(e) = plugin.shutdown().await {
+                errors.push((plugin_id, e));
+            }
+        }
+
+        self.clear_services();
+
+        errors
+    }
+
+    /// Unload all plugins
+    pub async fn shutdown_all(&mut self) -> lib_plugin_abi_v3::Result<()> {
+        for (plugin_id, e) in self.shutdown().await {
+            tracing::warn!(plugin_id, error = %e, "Error shutting down plugin");
+        }
+
+        Ok(())
+    }
+
+    /// Clear all service registries
+    fn clear_services(&mut self) {
         self.cli_commands.clear();
         self.http_routes.clear();
         self.language_analyzers.clear();
+        self.fallback_analyzer = None;
         self.embedders.clear();
         self.runners.clear();
         self.health_checks.clear();
@@ -329,13 +773,267 @@ impl PluginManagerV3 {
         self.rollout_strategies.clear();
         self.log_providers.clear();
         self.daemon_services.clear();
+    }
+}
 
-        Ok(())
+impl Drop for PluginManagerV3 {
+    fn drop(&mut self) {
+        self.clear_services();
+
+        for plugin_id in self.load_order.drain(..).rev() {
+            self.plugins.remove(&plugin_id);
+        }
+        self.plugins.clear();
+
+        while let Some(library) = self.libraries.pop() {
+            drop(library);
+        }
     }
 }
 
+/// A language analyzer found by [`PluginManagerV3::find_language_analyzer`]
+#[derive(Clone)]
+pub struct AnalyzerMatch {
+    /// The analyzer
+    pub analyzer: Arc<dyn lang::LanguageAnalyzer>,
+    /// Whether no analyzer was registered for the language and this is the fallback
+    pub is_fallback: bool,
+}
+
+/// What [`PluginManagerV3::select_embedder`] should pick
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmbedderRequirements {
+    /// Provider names in order of preference
+    pub provider_pref: Vec<String>,
+}
+
+/// Proxy middleware in application order, built by [`PluginManagerV3::build_middleware_chain`]
+///
+/// Apply the entries front to back around an `HttpRoutes` handler for requests,
+/// and back to front for responses.
+#[derive(Clone)]
+pub struct ComposedMiddleware {
+    chain: Vec<(String, Arc<dyn proxy::ProxyMiddleware>)>,
+}
+
+impl ComposedMiddleware {
+    /// Middleware names in application order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.chain.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Middleware in application order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Arc<dyn proxy::ProxyMiddleware>> {
+        self.chain.iter().map(|(_, middleware)| middleware)
+    }
+
+    /// Number of middleware in the chain
+    pub fn len(&self) -> usize {
+        self.chain.len()
+    }
+
+    /// Check if the chain is empty
+    pub fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+}
+
+/// The given plugin IDs that the installer no longer finds on disk
+fn not_installed<'a>(ids: impl IntoIterator<Item = &'a String>, installer: &PluginInstaller) -> Vec<String> {
+    ids.into_iter()
+        .filter(|id| installer.is_installed(id).is_none())
+        .cloned()
+        .collect()
+}
+
 impl Default for PluginManagerV3 {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_enable_reports_failures() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller::new("http://localhost", tmp.path().join("plugins"), tmp.path().join("cache"));
+
+        let version_dir = installer.plugin_path("adi.tasks").join("1.0.0");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(
+            version_dir.join("plugin.toml"),
+            r#"[plugin]
+id = "adi.tasks"
+name = "Tasks"
+version = "1.0.0"
+type = "core"
+
+[binary]
+name = "plugin"
+
+[compatibility]
+depends_on = ["adi.hive"]
+"#,
+        )
+        .unwrap();
+        std::fs::write(installer.plugin_path("adi.tasks").join(".version"), "1.0.0").unwrap();
+
+        let preflight = PluginManagerV3::new().can_enable(&installer, "adi.tasks");
+        assert!(!preflight.can_enable());
+
+        let failed: Vec<_> = preflight.failures().map(|c| c.name).collect();
+        assert_eq!(failed, vec!["binary", "dependencies"]);
+
+        let missing = PluginManagerV3::new().can_enable(&installer, "adi.missing");
+        assert_eq!(missing.failures().next().unwrap().name, "manifest");
+    }
+
+    #[tokio::test]
+    async fn test_load_uses_configured_binary_resolver() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_binary_resolver(|manifest, dir| {
+                Some(dir.join(format!("{}-{}.bin", manifest.binary.name, manifest.plugin.version)))
+            });
+        let installer = PluginInstaller::from_config(&config);
+
+        let version_dir = installer.plugin_path("adi.hive").join("1.0.0");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(
+            version_dir.join("plugin.toml"),
+            "[plugin]\nid = \"adi.hive\"\nname = \"Hive\"\nversion = \"1.0.0\"\ntype = \"core\"\n\n[binary]\nname = \"plugin\"\n",
+        )
+        .unwrap();
+        std::fs::write(version_dir.join("plugin-1.0.0.bin"), b"not a library").unwrap();
+        std::fs::write(installer.plugin_path("adi.hive").join(".version"), "1.0.0").unwrap();
+
+        let mut manager = PluginManagerV3::new();
+        assert!(manager.can_enable(&installer, "adi.hive").can_enable());
+
+        // The binary is found, so loading gets as far as dlopen rejecting the file
+        let err = manager.load_installed(&installer, "adi.hive").await.unwrap_err();
+        assert!(matches!(&err, HostError::InitFailed(msg) if msg.contains("plugin-1.0.0.bin")), "{err}");
+        assert!(!manager.is_loaded("adi.hive"));
+    }
+
+    #[tokio::test]
+    async fn test_only_binary_failures_count_toward_quarantine() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = crate::PluginConfig::new(tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_binary_resolver(|manifest, dir| Some(dir.join(format!("{}.bin", manifest.binary.name))));
+        let installer = PluginInstaller::from_config(&config);
+
+        for (id, deps) in [("adi.hive", ""), ("adi.tasks", "depends_on = [\"adi.missing\"]")] {
+            let version_dir = installer.plugin_path(id).join("1.0.0");
+            std::fs::create_dir_all(&version_dir).unwrap();
+            std::fs::write(
+                version_dir.join("plugin.toml"),
+                format!(
+                    "[plugin]\nid = \"{id}\"\nname = \"Test\"\nversion = \"1.0.0\"\ntype = \"core\"\n\n\
+                     [binary]\nname = \"plugin\"\n\n[compatibility]\n{deps}\n"
+                ),
+            )
+            .unwrap();
+            std::fs::write(version_dir.join("plugin.bin"), b"not a library").unwrap();
+            std::fs::write(installer.plugin_path(id).join(".version"), "1.0.0").unwrap();
+        }
+
+        let mut manager = PluginManagerV3::new();
+        let results = manager.load_all_installed(&installer).await.unwrap();
+        assert!(results.iter().all(|(_, result)| result.is_err()));
+
+        // The broken binary counts; the missing dependency does not
+        assert_eq!(installer.load_failures("adi.hive"), 1);
+        assert_eq!(installer.load_failures("adi.tasks"), 0);
+    }
+
+    #[test]
+    fn test_orphaned_after_install_removed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller::new("http://localhost", tmp.path().join("plugins"), tmp.path().join("cache"));
+        for id in ["adi.hive", "adi.tasks"] {
+            let version_dir = installer.plugin_path(id).join("1.0.0");
+            std::fs::create_dir_all(&version_dir).unwrap();
+            std::fs::write(installer.plugin_path(id).join(".version"), "1.0.0").unwrap();
+        }
+        let loaded = ["adi.hive".to_string(), "adi.tasks".to_string()];
+        assert!(not_installed(&loaded, &installer).is_empty());
+
+        std::fs::remove_dir_all(installer.plugin_path("adi.tasks")).unwrap();
+        assert_eq!(not_installed(&loaded, &installer), vec!["adi.tasks"]);
+        assert!(PluginManagerV3::new().orphaned_plugins(&installer).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_probe_not_installed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller::new("http://localhost", tmp.path().join("plugins"), tmp.path().join("cache"));
+
+        let result = PluginManagerV3::new().probe_installed(&installer, "adi.hive").await;
+        assert!(matches!(result, Err(HostError::NotInstalled(_))));
+    }
+
+    #[tokio::test]
+    async fn test_uninstall_not_loaded() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller::new("http://localhost", tmp.path().join("plugins"), tmp.path().join("cache"));
+        let version_dir = installer.plugin_path("adi.hive").join("1.0.0");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(version_dir.join("plugin.toml"), "").unwrap();
+        std::fs::write(installer.plugin_path("adi.hive").join(".version"), "1.0.0").unwrap();
+
+        let mut manager = PluginManagerV3::new();
+        manager.uninstall(&installer, "adi.hive").await.unwrap();
+        assert!(!installer.plugin_path("adi.hive").exists());
+
+        let result = manager.uninstall(&installer, "adi.hive").await;
+        assert!(matches!(result, Err(HostError::NotInstalled(_))));
+    }
+
+    #[tokio::test]
+    async fn test_uninstall_refused_before_hook() {
+        let tmp = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller::new("http://localhost", tmp.path().join("plugins"), tmp.path().join("cache"))
+            .with_lock_timeout(std::time::Duration::from_millis(200));
+        let version_dir = installer.plugin_path("adi.hive").join("1.0.0");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(version_dir.join("plugin.toml"), "").unwrap();
+        std::fs::write(installer.plugin_path("adi.hive").join(".version"), "1.0.0").unwrap();
+        let _held = crate::installer::InstallLock::acquire(installer.install_dir(), "adi.hive", std::time::Duration::ZERO)
+            .await
+            .unwrap();
+
+        let result = PluginManagerV3::new().uninstall(&installer, "adi.hive").await;
+        assert!(matches!(result, Err(HostError::InstallInProgress(_))));
+        assert!(installer.is_installed("adi.hive").is_some());
+    }
+
+    #[test]
+    fn test_language_for_path() {
+        let mut manager = PluginManagerV3::new();
+        let language = |m: &PluginManagerV3, path: &str| m.language_for_path(std::path::Path::new(path));
+
+        assert_eq!(language(&manager, "src/main.rs").as_deref(), Some("rust"));
+        assert_eq!(language(&manager, "App.TSX").as_deref(), Some("typescript"));
+        assert_eq!(language(&manager, "main.go").as_deref(), Some("go"));
+        assert_eq!(language(&manager, "Makefile"), None);
+
+        manager.register_analyzer_extension("H", "cpp");
+        assert_eq!(language(&manager, "lib.h").as_deref(), Some("cpp"));
+        assert!(manager.analyzer_for_path(std::path::Path::new("lib.h")).is_none());
+    }
+
+    #[test]
+    fn test_middleware_chain_lists_missing() {
+        let manager = PluginManagerV3::new();
+
+        assert!(manager.build_middleware_chain(&[]).unwrap().is_empty());
+        match manager.build_middleware_chain(&["auth".to_string(), "cors".to_string()]) {
+            Err(HostError::MissingMiddleware(missing)) => assert_eq!(missing, vec!["auth", "cors"]),
+            _ => panic!("expected MissingMiddleware"),
+        }
+    }
+}
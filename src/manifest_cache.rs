@@ -0,0 +1,210 @@
+//! Parsed manifest cache keyed by path, mtime and size.
+//!
+//! Re-scanning the plugins directory re-reads the same `plugin.toml` files over
+//! and over. The cache keeps the parsed [`PluginManifest`] and only re-parses a
+//! file when its modification time or size changes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use lib_plugin_manifest::PluginManifest;
+
+use crate::HostError;
+
+/// Default limit on the size of a `plugin.toml` that will be parsed.
+pub const DEFAULT_MAX_MANIFEST_SIZE: u64 = 256 * 1024;
+
+/// Hit/miss counters for a [`ManifestCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ManifestCacheStats {
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups that had to parse the file
+    pub misses: u64,
+}
+
+struct CachedManifest {
+    mtime: SystemTime,
+    size: u64,
+    manifest: PluginManifest,
+}
+
+/// Cache of parsed plugin manifests.
+#[derive(Default)]
+pub struct ManifestCache {
+    entries: Mutex<HashMap<PathBuf, CachedManifest>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    max_size: Option<u64>,
+}
+
+impl ManifestCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuse to parse manifests larger than `bytes`.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// The configured size limit, if any.
+    pub(crate) fn max_size(&self) -> Option<u64> {
+        self.max_size
+    }
+
+    /// Load a manifest, reusing the cached parse if the file is unchanged.
+    pub fn load(&self, path: &Path) -> Result<PluginManifest, HostError> {
+        let metadata = match checked_metadata(path, self.max_size) {
+            Ok(m) => m,
+            Err(e) => {
+                self.entries.lock().unwrap().remove(path);
+                return Err(e);
+            }
+        };
+        let mtime = metadata.modified()?;
+        let size = metadata.len();
+
+        if let Some(cached) = self.entries.lock().unwrap().get(path) {
+            if cached.mtime == mtime && cached.size == size {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(cached.manifest.clone());
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let manifest = PluginManifest::from_file(path)?;
+        self.entries.lock().unwrap().insert(
+            path.to_path_buf(),
+            CachedManifest {
+                mtime,
+                size,
+                manifest: manifest.clone(),
+            },
+        );
+        Ok(manifest)
+    }
+
+    /// Read a manifest's text without parsing it, subject to the size limit.
+    pub fn read_raw(&self, path: &Path) -> Result<String, HostError> {
+        check_manifest_size(path, self.max_size)?;
+        Ok(std::fs::read_to_string(path)?)
+    }
+
+    /// Drop entries whose files no longer exist.
+    pub fn prune(&self) {
+        self.entries.lock().unwrap().retain(|path, _| path.exists());
+    }
+
+    /// Current hit/miss counters.
+    pub fn stats(&self) -> ManifestCacheStats {
+        ManifestCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Fail with [`HostError::ManifestTooLarge`] if the file at `path` exceeds `limit`.
+pub(crate) fn check_manifest_size(path: &Path, limit: Option<u64>) -> Result<(), HostError> {
+    checked_metadata(path, limit).map(|_| ())
+}
+
+fn checked_metadata(path: &Path, limit: Option<u64>) -> Result<std::fs::Metadata, HostError> {
+    let metadata = std::fs::metadata(path)?;
+    let size = metadata.len();
+    match limit.filter(|&limit| size > limit) {
+        Some(limit) => Err(HostError::ManifestTooLarge {
+            path: path.to_path_buf(),
+            size,
+            limit,
+        }),
+        None => Ok(metadata),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_manifest(path: &Path, version: &str) {
+        let toml = format!(
+            r#"[plugin]
+id = "adi.test"
+name = "Test"
+version = "{version}"
+type = "core"
+
+[binary]
+name = "plugin"
+"#
+        );
+        fs::write(path, toml).unwrap();
+    }
+
+    #[test]
+    fn test_unchanged_manifest_is_cached() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("plugin.toml");
+        write_manifest(&path, "1.0.0");
+
+        let cache = ManifestCache::new();
+        cache.load(&path).unwrap();
+        cache.load(&path).unwrap();
+
+        assert_eq!(cache.stats(), ManifestCacheStats { hits: 1, misses: 1 });
+    }
+
+    #[test]
+    fn test_oversized_manifest_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("plugin.toml");
+        write_manifest(&path, "1.0.0");
+        let mut toml = fs::read_to_string(&path).unwrap();
+        toml.push_str(&format!("# {}\n", "x".repeat(4096)));
+        fs::write(&path, toml).unwrap();
+
+        let cache = ManifestCache::new().with_max_size(1024);
+        assert!(matches!(
+            cache.load(&path),
+            Err(HostError::ManifestTooLarge { limit: 1024, .. })
+        ));
+        assert_eq!(cache.stats(), ManifestCacheStats { hits: 0, misses: 0 });
+    }
+
+    #[test]
+    fn test_changed_manifest_is_reparsed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("plugin.toml");
+        write_manifest(&path, "1.0.0");
+
+        let cache = ManifestCache::new();
+        cache.load(&path).unwrap();
+
+        write_manifest(&path, "1.10.0");
+        let manifest = cache.load(&path).unwrap();
+
+        assert_eq!(manifest.plugin.version, "1.10.0");
+        assert_eq!(cache.stats(), ManifestCacheStats { hits: 0, misses: 2 });
+    }
+
+    #[test]
+    fn test_removed_manifest_is_evicted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("plugin.toml");
+        write_manifest(&path, "1.0.0");
+
+        let cache = ManifestCache::new();
+        cache.load(&path).unwrap();
+
+        fs::remove_file(&path).unwrap();
+        assert!(cache.load(&path).is_err());
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
+}
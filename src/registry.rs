@@ -0,0 +1,311 @@
+//! Registry backend abstraction.
+//!
+//! [`PluginInstaller`](crate::PluginInstaller) talks to the registry through
+//! [`RegistryBackend`], so offline/filesystem registries or test doubles can be
+//! swapped in for the HTTP [`RegistryClient`].
+
+use std::future::Future;
+
+use async_trait::async_trait;
+use registry_client::{
+    PluginEntry, PluginInfo, RegistryClient, RegistryError, SearchKind, SearchResults,
+};
+
+use crate::HostError;
+
+/// Download progress callback: `(bytes_done, bytes_total)`.
+pub type ProgressFn<'a> = &'a (dyn Fn(u64, u64) + Send + Sync);
+
+/// A downloaded plugin archive.
+#[derive(Debug, Clone)]
+pub struct Download {
+    /// Archive bytes as delivered, still compressed
+    pub bytes: Vec<u8>,
+    /// URL of the registry that served them, if the backend knows it
+    pub source: Option<String>,
+    /// Hex-encoded SHA-256 the registry publishes for the archive, if any
+    pub checksum: Option<String>,
+}
+
+/// Source of plugin metadata and archives.
+#[async_trait]
+pub trait RegistryBackend: Send + Sync {
+    /// Search the registry across all kinds.
+    async fn search(&self, query: &str) -> Result<SearchResults, HostError>;
+
+    /// List all available plugins.
+    async fn list_plugins(&self) -> Result<Vec<PluginEntry>, HostError>;
+
+    /// Get the latest version of a plugin.
+    ///
+    /// Return `HostError::Registry(RegistryError::NotFound(_))` for unknown plugins.
+    async fn get_plugin_latest(&self, id: &str) -> Result<PluginInfo, HostError>;
+
+    /// Get a specific version of a plugin.
+    async fn get_plugin_version(&self, id: &str, version: &str) -> Result<PluginInfo, HostError>;
+
+    /// Download a plugin archive for a platform.
+    async fn download_plugin(
+        &self,
+        id: &str,
+        version: &str,
+        platform: &str,
+        on_progress: ProgressFn<'_>,
+    ) -> Result<Download, HostError>;
+}
+
+#[async_trait]
+impl RegistryBackend for RegistryClient {
+    async fn search(&self, query: &str) -> Result<SearchResults, HostError> {
+        Ok(RegistryClient::search(self, query, SearchKind::All).await?)
+    }
+
+    async fn list_plugins(&self) -> Result<Vec<PluginEntry>, HostError> {
+        Ok(RegistryClient::list_plugins(self).await?)
+    }
+
+    async fn get_plugin_latest(&self, id: &str) -> Result<PluginInfo, HostError> {
+        Ok(RegistryClient::get_plugin_latest(self, id).await?)
+    }
+
+    async fn get_plugin_version(&self, id: &str, version: &str) -> Result<PluginInfo, HostError> {
+        Ok(RegistryClient::get_plugin_version(self, id, version).await?)
+    }
+
+    async fn download_plugin(
+        &self,
+        id: &str,
+        version: &str,
+        platform: &str,
+        on_progress: ProgressFn<'_>,
+    ) -> Result<Download, HostError> {
+        let bytes = RegistryClient::download_plugin(self, id, version, platform, |done, total| {
+            on_progress(done, total)
+        })
+        .await?;
+        // The HTTP registry returns bare archive bytes and publishes no checksum.
+        Ok(Download {
+            bytes,
+            source: None,
+            checksum: None,
+        })
+    }
+}
+
+/// Registry backend that tries an ordered list of registries until one succeeds.
+///
+/// The first entry is the primary registry, the rest are mirrors. Only
+/// transport failures move on to the next registry; the last error is
+/// returned once all of them have failed.
+pub struct MirroredRegistry {
+    registries: Vec<(String, Box<dyn RegistryBackend>)>,
+}
+
+impl MirroredRegistry {
+    /// Create from `(url, backend)` pairs in priority order.
+    pub fn new(registries: Vec<(String, Box<dyn RegistryBackend>)>) -> Self {
+        Self { registries }
+    }
+
+    /// Run `call` against each registry in order, returning the first success
+    /// or the first error that another registry wouldn't fix.
+    async fn first_success<'s, T, F, Fut>(&'s self, call: F) -> Result<T, HostError>
+    where
+        F: Fn(&'s str, &'s dyn RegistryBackend) -> Fut,
+        Fut: Future<Output = Result<T, HostError>>,
+    {
+        let mut last_err = HostError::LoadFailed("No registries configured".to_string());
+        for (url, backend) in &self.registries {
+            match call(url, backend.as_ref()).await {
+                Ok(value) => return Ok(value),
+                Err(e) if !is_retryable(&e) => return Err(e),
+                Err(e) => {
+                    tracing::warn!(registry = %url, error = %e, "Registry request failed, trying next");
+                    last_err = e;
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Create HTTP registry clients for each URL, sharing one cache directory.
+    pub fn from_urls(urls: &[String], cache_dir: &std::path::Path) -> Self {
+        Self::new(
+            urls.iter()
+                .map(|url| {
+                    let client = RegistryClient::new(url).with_cache(cache_dir.to_path_buf());
+                    (url.clone(), Box::new(client) as Box<dyn RegistryBackend>)
+                })
+                .collect(),
+        )
+    }
+}
+
+#[async_trait]
+impl RegistryBackend for MirroredRegistry {
+    async fn search(&self, query: &str) -> Result<SearchResults, HostError> {
+        self.first_success(|_, backend| backend.search(query)).await
+    }
+
+    async fn list_plugins(&self) -> Result<Vec<PluginEntry>, HostError> {
+        self.first_success(|_, backend| backend.list_plugins())
+            .await
+    }
+
+    async fn get_plugin_latest(&self, id: &str) -> Result<PluginInfo, HostError> {
+        self.first_success(|_, backend| backend.get_plugin_latest(id))
+            .await
+    }
+
+    async fn get_plugin_version(&self, id: &str, version: &str) -> Result<PluginInfo, HostError> {
+        self.first_success(|_, backend| backend.get_plugin_version(id, version))
+            .await
+    }
+
+    async fn download_plugin(
+        &self,
+        id: &str,
+        version: &str,
+        platform: &str,
+        on_progress: ProgressFn<'_>,
+    ) -> Result<Download, HostError> {
+        self.first_success(|url, backend| async move {
+            let download = backend
+                .download_plugin(id, version, platform, on_progress)
+                .await?;
+            Ok(Download {
+                source: download.source.or_else(|| Some(url.to_string())),
+                ..download
+            })
+        })
+        .await
+    }
+}
+
+/// Whether a failed registry request is worth retrying against a mirror.
+///
+/// IO errors and registry errors other than `NotFound` count as transport
+/// failures. A registry that answered "not found", and errors raised outside
+/// the registry layer, are final.
+fn is_retryable(error: &HostError) -> bool {
+    match error {
+        HostError::Io(_) => true,
+        HostError::Registry(RegistryError::NotFound(_)) => false,
+        HostError::Registry(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum Mock {
+        Archive(&'static [u8]),
+        Unreachable,
+        NotFound,
+    }
+
+    struct MockRegistry {
+        response: Mock,
+    }
+
+    #[async_trait]
+    impl RegistryBackend for MockRegistry {
+        async fn search(&self, query: &str) -> Result<SearchResults, HostError> {
+            Err(HostError::PackageNotFound(query.to_string()))
+        }
+
+        async fn list_plugins(&self) -> Result<Vec<PluginEntry>, HostError> {
+            Err(HostError::PackageNotFound(String::new()))
+        }
+
+        async fn get_plugin_latest(&self, id: &str) -> Result<PluginInfo, HostError> {
+            Err(HostError::PackageNotFound(id.to_string()))
+        }
+
+        async fn get_plugin_version(&self, id: &str, _: &str) -> Result<PluginInfo, HostError> {
+            Err(HostError::PackageNotFound(id.to_string()))
+        }
+
+        async fn download_plugin(
+            &self,
+            id: &str,
+            _: &str,
+            _: &str,
+            _: ProgressFn<'_>,
+        ) -> Result<Download, HostError> {
+            match self.response {
+                Mock::Archive(bytes) => Ok(Download {
+                    bytes: bytes.to_vec(),
+                    source: None,
+                    checksum: None,
+                }),
+                Mock::Unreachable => {
+                    Err(HostError::Io(std::io::Error::other("connection refused")))
+                }
+                Mock::NotFound => Err(HostError::Registry(RegistryError::NotFound(id.to_string()))),
+            }
+        }
+    }
+
+    fn mirrored(responses: &[(&str, Mock)]) -> MirroredRegistry {
+        MirroredRegistry::new(
+            responses
+                .iter()
+                .map(|&(url, response)| {
+                    (
+                        url.to_string(),
+                        Box::new(MockRegistry { response }) as Box<dyn RegistryBackend>,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    async fn download(registry: &MirroredRegistry) -> Result<Download, HostError> {
+        registry
+            .download_plugin("adi.hive", "1.0.0", "linux-x86_64", &|_, _| {})
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_mirror_fallback() {
+        let registry = mirrored(&[
+            ("https://primary.example.com", Mock::Unreachable),
+            ("https://mirror.example.com", Mock::Archive(b"archive")),
+        ]);
+
+        let download = download(&registry).await.unwrap();
+
+        assert_eq!(download.bytes, b"archive");
+        assert_eq!(
+            download.source.as_deref(),
+            Some("https://mirror.example.com")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_not_retried() {
+        let registry = mirrored(&[
+            ("https://primary.example.com", Mock::NotFound),
+            ("https://mirror.example.com", Mock::Archive(b"archive")),
+        ]);
+
+        assert!(matches!(
+            download(&registry).await,
+            Err(HostError::Registry(RegistryError::NotFound(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_all_mirrors_exhausted() {
+        let registry = mirrored(&[
+            ("https://primary.example.com", Mock::Unreachable),
+            ("https://mirror.example.com", Mock::Unreachable),
+        ]);
+
+        assert!(matches!(download(&registry).await, Err(HostError::Io(_))));
+    }
+}
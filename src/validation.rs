@@ -0,0 +1,284 @@
+//! Consistency checks for installed plugins that don't load any binary.
+//!
+//! Intended for packaging pipelines and CI: errors mean the plugin cannot load,
+//! warnings flag things that are likely mistakes but not fatal.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use lib_plugin_manifest::PluginManifest;
+
+use crate::manifest_cache::check_manifest_size;
+use crate::{BinaryResolver, DEFAULT_MAX_MANIFEST_SIZE};
+
+/// How serious a [`ValidationIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValidationSeverity {
+    /// Likely mistake, but the plugin can still load
+    Warning,
+    /// The plugin cannot load as installed
+    Error,
+}
+
+/// A single problem found by [`validate_installed_plugin`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Issue severity
+    pub severity: ValidationSeverity,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: ValidationSeverity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: ValidationSeverity::Warning,
+            message: message.into(),
+        }
+    }
+
+    /// Check if this issue is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == ValidationSeverity::Error
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            ValidationSeverity::Error => write!(f, "error: {}", self.message),
+            ValidationSeverity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+/// Validate an installed plugin without loading it.
+///
+/// Checks that `.version` points at a version directory, the manifest parses,
+/// the declared binary exists for this platform, every `depends_on` entry is
+/// installed, and no service ID is provided twice. Manifests over
+/// [`DEFAULT_MAX_MANIFEST_SIZE`] are reported as unreadable.
+pub fn validate_installed_plugin(plugins_dir: &Path, plugin_id: &str) -> Vec<ValidationIssue> {
+    validate_with_resolver(
+        plugins_dir,
+        plugin_id,
+        &BinaryResolver::default(),
+        Some(DEFAULT_MAX_MANIFEST_SIZE),
+        |dep| plugins_dir.join(dep).join(".version").exists(),
+    )
+}
+
+/// [`validate_installed_plugin`], locating the binary with `resolver`,
+/// refusing manifests larger than `max_manifest_size` and looking up
+/// dependencies with `is_installed`.
+pub(crate) fn validate_with_resolver(
+    plugins_dir: &Path,
+    plugin_id: &str,
+    resolver: &BinaryResolver,
+    max_manifest_size: Option<u64>,
+    is_installed: impl Fn(&str) -> bool,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let plugin_dir = plugins_dir.join(plugin_id);
+
+    let Ok(version) = std::fs::read_to_string(plugin_dir.join(".version")) else {
+        issues.push(ValidationIssue::error(format!(
+            "{} is not installed (no .version file)",
+            plugin_id
+        )));
+        return issues;
+    };
+    let version = version.trim();
+
+    let version_dir = plugin_dir.join(version);
+    let manifest_path = version_dir.join("plugin.toml");
+    let manifest = match check_manifest_size(&manifest_path, max_manifest_size)
+        .and_then(|()| Ok(PluginManifest::from_file(&manifest_path)?))
+    {
+        Ok(m) => m,
+        Err(e) => {
+            issues.push(ValidationIssue::error(format!(
+                "Cannot read manifest for {} {}: {}",
+                plugin_id, version, e
+            )));
+            return issues;
+        }
+    };
+
+    if manifest.plugin.id != plugin_id {
+        issues.push(ValidationIssue::warning(format!(
+            "Manifest ID {} does not match install directory {}",
+            manifest.plugin.id, plugin_id
+        )));
+    }
+    if manifest.plugin.version != version {
+        issues.push(ValidationIssue::warning(format!(
+            "Manifest version {} does not match installed version {}",
+            manifest.plugin.version, version
+        )));
+    }
+
+    if let Err(e) = resolver.resolve(&manifest, &version_dir) {
+        issues.push(ValidationIssue::error(format!(
+            "Binary {} not found: {}",
+            manifest.binary.name, e
+        )));
+    }
+
+    for dep in &manifest.compatibility.depends_on {
+        if dep == plugin_id {
+            issues.push(ValidationIssue::warning(format!(
+                "{} lists itself in depends_on",
+                plugin_id
+            )));
+        } else if !is_installed(dep) {
+            issues.push(ValidationIssue::error(format!(
+                "Dependency {} is not installed",
+                dep
+            )));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for service in &manifest.provides {
+        if !seen.insert(service.id.as_str()) {
+            issues.push(ValidationIssue::error(format!(
+                "Service {} is provided more than once",
+                service.id
+            )));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn install(dir: &Path, plugin_id: &str, version: &str, extra: &str, with_binary: bool) {
+        let version_dir = dir.join(plugin_id).join(version);
+        fs::create_dir_all(&version_dir).unwrap();
+
+        let toml = format!(
+            r#"[plugin]
+id = "{plugin_id}"
+name = "Test"
+version = "{version}"
+type = "core"
+
+[binary]
+name = "plugin"
+{extra}"#
+        );
+        fs::write(version_dir.join("plugin.toml"), toml).unwrap();
+        fs::write(dir.join(plugin_id).join(".version"), version).unwrap();
+
+        if with_binary {
+            let binary = if cfg!(target_os = "windows") {
+                "plugin.dll"
+            } else if cfg!(target_os = "macos") {
+                "libplugin.dylib"
+            } else {
+                "libplugin.so"
+            };
+            fs::write(version_dir.join(binary), b"").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_valid_plugin_has_no_issues() {
+        let tmp = tempfile::tempdir().unwrap();
+        install(tmp.path(), "adi.hive", "0.8.8", "", true);
+
+        assert!(validate_installed_plugin(tmp.path(), "adi.hive").is_empty());
+    }
+
+    #[test]
+    fn test_not_installed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let issues = validate_installed_plugin(tmp.path(), "adi.hive");
+
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].is_error());
+    }
+
+    #[test]
+    fn test_missing_binary_is_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        install(tmp.path(), "adi.hive", "0.8.8", "", false);
+
+        let issues = validate_installed_plugin(tmp.path(), "adi.hive");
+        assert!(issues
+            .iter()
+            .any(|i| i.is_error() && i.message.contains("Binary")));
+    }
+
+    #[test]
+    fn test_custom_resolver_finds_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        install(tmp.path(), "adi.hive", "0.8.8", "", false);
+        let version_dir = tmp.path().join("adi.hive").join("0.8.8");
+        fs::write(version_dir.join("plugin-0.8.8.bin"), b"").unwrap();
+
+        let resolver = BinaryResolver::with_custom(|manifest, dir| {
+            Some(dir.join(format!(
+                "{}-{}.bin",
+                manifest.binary.name, manifest.plugin.version
+            )))
+        });
+        let installed = |dep: &str| tmp.path().join(dep).exists();
+        assert!(
+            validate_with_resolver(tmp.path(), "adi.hive", &resolver, None, installed).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_oversized_manifest_is_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        install(tmp.path(), "adi.hive", "0.8.8", "", true);
+
+        let issues = validate_with_resolver(
+            tmp.path(),
+            "adi.hive",
+            &BinaryResolver::default(),
+            Some(16),
+            |_| true,
+        );
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].is_error() && issues[0].message.contains("byte limit"));
+    }
+
+    #[test]
+    fn test_dangling_dependency_and_duplicate_service() {
+        let tmp = tempfile::tempdir().unwrap();
+        let extra = r#"
+[compatibility]
+depends_on = ["adi.missing"]
+
+[[provides]]
+id = "adi.search"
+version = "1.0.0"
+
+[[provides]]
+id = "adi.search"
+version = "1.0.0"
+"#;
+        install(tmp.path(), "adi.tasks", "0.5.0", extra, true);
+
+        let issues = validate_installed_plugin(tmp.path(), "adi.tasks");
+        let errors: Vec<_> = issues.iter().filter(|i| i.is_error()).collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|i| i.message.contains("adi.missing")));
+        assert!(errors.iter().any(|i| i.message.contains("adi.search")));
+    }
+}